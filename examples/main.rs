@@ -116,6 +116,13 @@ pub fn main() -> Result<(), Error> {
         render.end_draw(&mut cam)?;
     }
 
+    // report any resources that are still loaded at shutdown
+    print!("{}", render.texture_manager.stats());
+    print!("{}", render.font_manager.stats());
+    print!("{}", render.font_manager.text_stats());
+    print!("{}", audio.sfx.stats());
+    print!("{}", audio.music.stats());
+
     Ok(())
 }
 
@@ -40,7 +40,7 @@ impl ImageLayer {
                 match a.key.as_ref() {
                     b"repeatx" => self.repeat_x = get_value::<i32>(&a.value)? == 1,
                     b"repeaty" => self.repeat_y = get_value::<i32>(&a.value)? == 1,
-                    _ => println!("warning: unrecognized atrribute {:?}", a.key),
+                    _ => log::warn!("unrecognized attribute {:?}", a.key),
                 }
             }
         }
@@ -53,7 +53,7 @@ impl ImageLayer {
                 b"source" => self.image_path.push(get_string(&a.value)?),
                 b"width" => self.width = get_value(&a.value)?,
                 b"height" => self.height = get_value(&a.value)?,
-                _ => println!("warning: unrecognized atrribute {:?}", a.key),
+                _ => log::warn!("unrecognized attribute {:?}", a.key),
             }
         }
         Ok(())
@@ -65,14 +65,14 @@ impl HandleXml for ImageLayer {
     fn empty(&mut self, e : &BytesStart) -> Result<(), TiledError> {
         match e.name().as_ref() {
             b"image" => self.parse_image_attributes(collect_attribs(&e)?)?,
-            _ => println!("unrecognized empty tag {:?}", e.name()),
+            _ => log::info!("unrecognized empty tag {:?}", e.name()),
         }
         Ok(())
     }
     fn start(&mut self, e : &BytesStart, reader: &mut Reader<&[u8]>) -> Result<(), TiledError> {
         match e.name().as_ref() {
             b"properties" => parse_xml(&mut self.props, reader)?,
-            _ => println!("unrecognized tag {:?}", e.name()),
+            _ => log::info!("unrecognized tag {:?}", e.name()),
         }
         Ok(())
     }
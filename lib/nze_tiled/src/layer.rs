@@ -55,7 +55,7 @@ impl Layer {
                 match a.key.as_ref() {
                     b"width" => self.width = get_value(&a.value)?,
                     b"height" => self.height = get_value(&a.value)?,
-                    _ => println!("warning: unrecognized atrribute {:?}", a.key),
+                    _ => log::warn!("unrecognized attribute {:?}", a.key),
                 }
             }
         }
@@ -68,7 +68,7 @@ impl HandleXml for Layer {
         match e.name().as_ref() {
             b"data" => parse_xml(&mut self.tiles, reader)?,
             b"properties" => parse_xml(&mut self.props, reader)?,
-            _ => println!("unrecognized tag {:?}", e.name()),
+            _ => log::info!("unrecognized tag {:?}", e.name()),
         }
         Ok(())
     }
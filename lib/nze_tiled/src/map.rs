@@ -119,7 +119,7 @@ impl Map {
                     b"left-up" => RenderOrder::LeftUp,
                     _ => { return Err(TiledError::UnsupportedType()); },
                 },
-                _ => println!("warning: unrecognized atrribute {:?}", a.key),
+                _ => log::warn!("unrecognized attribute {:?}", a.key),
             }
         }
         Ok(())
@@ -150,7 +150,7 @@ impl HandleXml for Map {
                 self.img_layers.push(ImageLayer::new(collect_attribs(&e)?, reader, self.current_layer)?);
                 self.current_layer += 1;
             },
-            _ => println!("unrecognized tag {:?}", e.name()),
+            _ => log::info!("unrecognized tag {:?}", e.name()),
         }
         Ok(())
     }
@@ -162,7 +162,7 @@ impl HandleXml for Map {
                 );
                 self.total_tiles += self.tilesets.last().unwrap().tile_count;
             },
-            _ => println!("unrecognized empty tag {:?}", e.name()),
+            _ => log::info!("unrecognized empty tag {:?}", e.name()),
         }
         Ok(())
     }
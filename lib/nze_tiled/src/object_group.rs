@@ -121,7 +121,7 @@ impl Obj {
                 b"visible" => self.info.visible = get_string(&a.value)? == "1",
                 b"rotation" => self.rotation = get_value(&a.value)?,
                 b"template" => self.template = Some(PathBuf::from(get_string(&a.value)?)),
-                _ => println!("warning: unrecognized atrribute {:?}", a.key),
+                _ => log::warn!("unrecognized attribute {:?}", a.key),
             }
         }
         Ok(())
@@ -135,7 +135,7 @@ impl HandleXml for Obj {
             b"object" => self.parse_attribs(collect_attribs(&e)?)?,
             b"properties" => parse_xml(&mut self.props, reader)?,
             b"text" => self.text = Some(Box::new(Text::new(collect_attribs(&e)?, reader)?)),
-            _ => println!("unrecognized tag {:?}", e.name()),
+            _ => log::info!("unrecognized tag {:?}", e.name()),
         }
         Ok(())
     }
@@ -146,7 +146,7 @@ impl HandleXml for Obj {
             b"polygon" => self.poly = Some(Box::new(Poly::new(collect_attribs(&e)?, true)?)),
             b"ellipse" => self.ellipse = true,
             b"point" => self.point = true,
-            _ => println!("unrecognized empty tag {:?}", e.name()),
+            _ => log::info!("unrecognized empty tag {:?}", e.name()),
         }
         Ok(())
     }
@@ -176,7 +176,7 @@ impl Poly {
                         self.points.push(Vec2::new(x, y));
                     }
                 }
-                _ => println!("warning: unrecognized atrribute {:?}", a.key),
+                _ => log::warn!("unrecognized attribute {:?}", a.key),
             }
         }
         Ok(())
@@ -226,7 +226,7 @@ impl Text {
                     },
                 },
                 b"color" => self.colour = get_colour(&a.value)?,
-                _ => println!("warning: unrecognized atrribute {:?}", a.key),
+                _ => log::warn!("unrecognized attribute {:?}", a.key),
             }
         }
         Ok(())
@@ -269,7 +269,7 @@ impl ObjGroup {
     fn parse_attribs(&mut self, attribs : Vec<Attribute>) -> Result<(), TiledError> {
         for a in attribs {
             if let Some(()) = self.info.handle_attrib(&a)? {
-                println!("warning: unrecognized atrribute {:?}", a.key);
+                log::warn!("unrecognized attribute {:?}", a.key);
             }
         }
         Ok(())
@@ -311,14 +311,14 @@ impl HandleXml for ObjGroup {
         match e.name().as_ref() {
             b"properties" => parse_xml(&mut self.props, reader)?,
             b"object" => self.objs.push(Obj::new(collect_attribs(&e)?, Some(reader), &self.path)?),
-            _ => println!("unrecognized tag {:?}", e.name()),
+            _ => log::info!("unrecognized tag {:?}", e.name()),
         }
         Ok(())
     }
     fn empty(&mut self, e : &BytesStart) -> Result<(), TiledError> {
         match e.name().as_ref() {
              b"object" => self.objs.push(Obj::new(collect_attribs(&e)?, None, &self.path)?),
-            _ => println!("unrecognized empty tag {:?}", e.name()),
+            _ => log::info!("unrecognized empty tag {:?}", e.name()),
         }
         Ok(())
     }
@@ -31,7 +31,7 @@ impl Properties {
                      "bool" => Some(PropertyType::Bool),
                      "int" => Some(PropertyType::Int),
                      _ =>  {
-                         println!("warning: unrecognized type {:?}", get_string(&a.value)?);
+                         log::warn!("unrecognized type {:?}", get_string(&a.value)?);
                          None
                      }, 
                  },
@@ -58,7 +58,7 @@ impl Properties {
                      },
                      None => { return Err(TiledError::UnsupportedType()); },
                  }
-                 _ => println!("warning: unrecognized atrribute {:?}", a.key),
+                 _ => log::warn!("unrecognized attribute {:?}", a.key),
              }
         }
         Ok(())
@@ -69,7 +69,7 @@ impl HandleXml for Properties {
     fn empty(&mut self, e : &BytesStart) -> Result<(), TiledError> {
         match e.name().as_ref() {
             b"property" => self.add_property(collect_attribs(&e)?)?,
-            _ => println!("unrecognized empty tag {:?}", e.name()),
+            _ => log::info!("unrecognized empty tag {:?}", e.name()),
         }
         Ok(())
     }
@@ -67,7 +67,7 @@ impl Tileset {
                 b"columns" => self.column_count = get_value(&a.value)?,
                 b"version" => self.version = get_string(&a.value)?.to_string(),
                 b"tiledversion" => self.tiledversion = get_string(&a.value)?.to_string(),
-                _ => println!("warning: unrecognized attribute {:?}", a.key),
+                _ => log::warn!("unrecognized attribute {:?}", a.key),
             }
         }
         Ok(())
@@ -79,7 +79,7 @@ impl Tileset {
                 b"source" => self.image_path.push(get_string(&a.value)?),
                 b"width" => self.image_width = get_value(&a.value)?,
                 b"height" => self.image_height = get_value(&a.value)?,
-                _ => println!("warning: unrecognized attribute {:?}", a.key),
+                _ => log::warn!("unrecognized attribute {:?}", a.key),
             }
         }
         Ok(())
@@ -122,7 +122,7 @@ impl Tileset {
                         })?
                     )?;
                 }
-                _  => println!("warning: unrecognized atrribute {:?}", a.key),
+                _  => log::warn!("unrecognized attribute {:?}", a.key),
             }
         }
         Ok(tileset)
@@ -133,14 +133,14 @@ impl HandleXml for Tileset {
     fn start(&mut self, e : &BytesStart, _: &mut Reader<&[u8]>) -> Result<(), TiledError> {
         match e.name().as_ref() {
             b"tileset" => self.parse_tileset_attribs(collect_attribs(&e)?)?,
-            _ => println!("unrecognized tag {:?}", e.name()),
+            _ => log::info!("unrecognized tag {:?}", e.name()),
         }
         Ok(())
     }
     fn empty(&mut self, e : &BytesStart) -> Result<(), TiledError> {
         match e.name().as_ref() {
             b"image" => self.parse_image_attribs(collect_attribs(&e)?)?,
-            _ => println!("unrecognized empty tag {:?}", e.name()),
+            _ => log::info!("unrecognized empty tag {:?}", e.name()),
         }
         Ok(())
     }
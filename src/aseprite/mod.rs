@@ -0,0 +1,322 @@
+//! For loading sprites and animations directly from [Aseprite](https://www.aseprite.org/) files
+//!
+//! The frames of the file are composited and packed into a single texture,
+//! and the file's tags become [Animation]s that give the frame to draw at a given time.
+//!
+//! Note: only the normal blend mode is supported, and tilemap layers are not drawn
+
+use std::path::Path;
+
+use crate::{Error, GameObject, resource, manager::TextureManager, resources::pixels::blend_over};
+use crate::geometry::*;
+
+mod parse;
+
+use parse::{AseFile, AseTag, CelContent};
+
+/// The direction an [Animation] plays its frames in
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoopDirection {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+/// A single frame of a [Sprite], with where it is in the sprite's texture
+#[derive(Clone, Copy)]
+pub struct Frame {
+    pub tex_rect: Rect,
+    /// how long the frame is shown for, in seconds
+    pub duration: f64,
+}
+
+/// An animation made from a tag in the aseprite file
+#[derive(Clone)]
+pub struct Animation {
+    pub name: String,
+    /// indices into the sprite's frames for one play through, in the order they are shown
+    pub frames: Vec<usize>,
+    pub direction: LoopDirection,
+    /// number of times the animation plays, `0` loops forever
+    pub repeat: u16,
+}
+
+/// A named area of the sprite, ie a hitbox or a 9-patch
+#[derive(Clone)]
+pub struct Slice {
+    pub name: String,
+    pub keys: Vec<SliceKey>,
+}
+
+/// The shape of a [Slice] starting at a frame
+#[derive(Clone, Copy)]
+pub struct SliceKey {
+    pub frame: usize,
+    pub rect: Rect,
+    /// the centre part of a 9-patch slice
+    pub centre: Option<Rect>,
+    pub pivot: Option<Vec2>,
+}
+
+/// A sprite loaded from an .aseprite file
+#[derive(Clone)]
+pub struct Sprite {
+    pub texture: resource::Texture,
+    pub width: u32,
+    pub height: u32,
+    pub frames: Vec<Frame>,
+    pub animations: Vec<Animation>,
+    pub slices: Vec<Slice>,
+}
+
+impl Sprite {
+    /// Load the .aseprite file at the path,
+    /// and create a texture holding all of its frames with the [TextureManager]
+    ///
+    /// Will throw a `LoadFile` error if the file could not be read or parsed
+    pub fn new<TexType>(path: &Path, tex_manager: &mut TextureManager<TexType>) -> Result<Sprite, Error> {
+        let file = AseFile::load(path)?;
+        if file.frames.is_empty() || file.width == 0 || file.height == 0 {
+            return Err(Error::LoadFile(format!(
+                "aseprite file {} has no frames to draw", path.to_string_lossy())));
+        }
+        let (w, h) = (file.width as usize, file.height as usize);
+        let columns = (file.frames.len() as f64).sqrt().ceil() as usize;
+        let rows = file.frames.len().div_ceil(columns);
+        let sheet_width = w * columns;
+        let mut sheet = vec![0; sheet_width * h * rows * 4];
+        let mut frames = Vec::new();
+        for (i, frame) in file.frames.iter().enumerate() {
+            let (fx, fy) = ((i % columns) * w, (i / columns) * h);
+            let pixels = composite_frame(&file, i);
+            for y in 0..h {
+                let src = y * w * 4;
+                let dst = ((fy + y) * sheet_width + fx) * 4;
+                sheet[dst..dst + w * 4].copy_from_slice(&pixels[src..src + w * 4]);
+            }
+            frames.push(Frame {
+                tex_rect: Rect::new(fx as f64, fy as f64, w as f64, h as f64),
+                duration: frame.duration_ms as f64 / 1000.0,
+            });
+        }
+        let texture = tex_manager.create_texture(sheet_width as u32, (h * rows) as u32, &sheet)?;
+        let animations = file.tags.iter()
+            .map(|t| tag_animation(t, frames.len()).ok_or_else(|| Error::LoadFile(format!(
+                "aseprite file {} has tag {} with invalid frames {} to {}",
+                path.to_string_lossy(), t.name, t.from, t.to))))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Sprite {
+            texture,
+            width: file.width,
+            height: file.height,
+            frames,
+            animations,
+            slices: file.slices,
+        })
+    }
+
+    /// get the animation made from the tag with this name
+    pub fn animation(&self, name: &str) -> Option<&Animation> {
+        self.animations.iter().find(|a| a.name == name)
+    }
+
+    /// get the slice with this name
+    pub fn slice(&self, name: &str) -> Option<&Slice> {
+        self.slices.iter().find(|s| s.name == name)
+    }
+
+    /// a [GameObject] at the origin showing one frame of the sprite at its original size
+    pub fn game_object(&self, frame: usize) -> GameObject {
+        let mut obj = GameObject::new_from_tex(self.texture);
+        obj.rect = Rect::new(0.0, 0.0, self.width as f64, self.height as f64);
+        obj.tex_rect = Some(self.frames[frame].tex_rect);
+        obj
+    }
+}
+
+impl Animation {
+    /// the time in seconds for one play through of the animation
+    pub fn length(&self, frames: &[Frame]) -> f64 {
+        self.frames.iter().map(|f| frames[*f].duration).sum()
+    }
+
+    /// the index of the sprite's frame to show after the animation has played for `time` seconds
+    ///
+    /// once the animation has repeated `repeat` times, the last frame is shown,
+    /// and an animation with no frames always gives `0`
+    pub fn frame_at(&self, frames: &[Frame], time: f64) -> usize {
+        if self.frames.is_empty() {
+            return 0;
+        }
+        let length = self.length(frames);
+        if length <= 0.0 {
+            return self.frames[0];
+        }
+        if self.repeat != 0 && time >= length * self.repeat as f64 {
+            return *self.frames.last().unwrap();
+        }
+        let mut time = time.max(0.0) % length;
+        for f in self.frames.iter() {
+            time -= frames[*f].duration;
+            if time < 0.0 {
+                return *f;
+            }
+        }
+        *self.frames.last().unwrap()
+    }
+
+    /// the texture rect of the frame to show after the animation has played for `time` seconds
+    pub fn tex_rect_at(&self, sprite: &Sprite, time: f64) -> Rect {
+        sprite.frames[self.frame_at(&sprite.frames, time)].tex_rect
+    }
+}
+
+impl Slice {
+    /// the key in effect at the frame, keys last until the next key's frame
+    pub fn key_at(&self, frame: usize) -> Option<&SliceKey> {
+        self.keys.iter().rev().find(|k| k.frame <= frame)
+    }
+}
+
+/// the animation for a tag, or `None` if the tag's frames are not in the sprite
+///
+/// a tag ending past the last frame is cut short
+fn tag_animation(tag: &AseTag, frame_count: usize) -> Option<Animation> {
+    if tag.from > tag.to || tag.from >= frame_count {
+        return None;
+    }
+    Some(Animation {
+        name: tag.name.clone(),
+        frames: animation_frames(tag.from, tag.to.min(frame_count - 1), tag.direction),
+        direction: tag.direction,
+        repeat: tag.repeat,
+    })
+}
+
+fn animation_frames(from: usize, to: usize, direction: LoopDirection) -> Vec<usize> {
+    let forward: Vec<usize> = (from..=to).collect();
+    let reverse: Vec<usize> = forward.iter().rev().copied().collect();
+    // ping pong does not repeat the end frames when changing direction
+    let inner = |v: &Vec<usize>| {
+        if v.len() > 2 { v[1..v.len() - 1].to_vec() } else { Vec::new() }
+    };
+    match direction {
+        LoopDirection::Forward => forward,
+        LoopDirection::Reverse => reverse,
+        LoopDirection::PingPong => [forward, inner(&reverse)].concat(),
+        LoopDirection::PingPongReverse => [reverse, inner(&forward)].concat(),
+    }
+}
+
+/// draw each visible layer's cel for the frame into an rgba buffer
+fn composite_frame(file: &AseFile, frame: usize) -> Vec<u8> {
+    let (w, h) = (file.width as i32, file.height as i32);
+    let mut out = vec![0; (w * h * 4) as usize];
+    let visible = visible_layers(file);
+    let mut cels: Vec<_> = file.frames[frame].cels.iter()
+        .filter(|c| c.layer < file.layers.len() && visible[c.layer])
+        .collect();
+    cels.sort_by_key(|c| (c.layer as i32 + c.z_index as i32, c.z_index));
+    for cel in cels {
+        let layer = &file.layers[cel.layer];
+        let (cw, ch, pixels) = match cel_image(file, &cel.content, cel.layer) {
+            Some(i) => i,
+            None => continue,
+        };
+        let layer_opacity = if file.layer_opacity_valid { layer.opacity as u32 } else { 255 };
+        let opacity = cel.opacity as u32 * layer_opacity / 255;
+        let bpp = file.depth as usize / 8;
+        for y in 0..ch as i32 {
+            let ty = cel.y + y;
+            if ty < 0 || ty >= h { continue; }
+            for x in 0..cw as i32 {
+                let tx = cel.x + x;
+                if tx < 0 || tx >= w { continue; }
+                let i = (y as usize * cw as usize + x as usize) * bpp;
+                let mut src = pixel_colour(file, &pixels[i..i + bpp], layer.is_background());
+                src[3] = (src[3] as u32 * opacity / 255) as u8;
+                let dst = ((ty * w + tx) * 4) as usize;
+                blend_over(&mut out[dst..dst + 4], src);
+            }
+        }
+    }
+    out
+}
+
+/// a layer is visible if it and all of the groups it is in are visible
+fn visible_layers(file: &AseFile) -> Vec<bool> {
+    let mut group_visible: Vec<bool> = Vec::new();
+    let mut visible = Vec::new();
+    for layer in file.layers.iter() {
+        let level = layer.child_level as usize;
+        let parent = if level == 0 { true } else { *group_visible.get(level - 1).unwrap_or(&true) };
+        let this = parent && layer.is_visible();
+        group_visible.truncate(level);
+        group_visible.push(this);
+        visible.push(this && layer.is_image());
+    }
+    visible
+}
+
+fn cel_image<'a>(file: &'a AseFile, content: &'a CelContent, layer: usize) -> Option<(u32, u32, &'a [u8])> {
+    match content {
+        CelContent::Image { width, height, pixels } => Some((*width, *height, pixels)),
+        CelContent::Linked(frame) => {
+            let linked = file.frames.get(*frame)?.cels.iter().find(|c| c.layer == layer)?;
+            match &linked.content {
+                CelContent::Image { width, height, pixels } => Some((*width, *height, pixels)),
+                CelContent::Linked(_) => None,
+            }
+        }
+    }
+}
+
+fn pixel_colour(file: &AseFile, p: &[u8], background: bool) -> [u8; 4] {
+    match file.depth {
+        32 => [p[0], p[1], p[2], p[3]],
+        16 => [p[0], p[0], p[0], p[1]],
+        _ => {
+            if p[0] == file.transparent_index && !background {
+                [0, 0, 0, 0]
+            } else {
+                *file.palette.get(p[0] as usize).unwrap_or(&[0, 0, 0, 0])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod aseprite_tests {
+    use super::*;
+
+    fn tag(from: usize, to: usize, direction: LoopDirection) -> AseTag {
+        AseTag { from, to, direction, repeat: 0, name: "tag".to_string() }
+    }
+
+    fn frames(count: usize) -> Vec<Frame> {
+        vec![Frame { tex_rect: Rect::new(0.0, 0.0, 1.0, 1.0), duration: 0.1 }; count]
+    }
+
+    #[test]
+    fn test_tag_animation() {
+        let a = tag_animation(&tag(1, 3, LoopDirection::PingPong), 4).unwrap();
+        assert!(a.frames == vec![1, 2, 3, 2]);
+        let a = tag_animation(&tag(2, 9, LoopDirection::Reverse), 4).unwrap();
+        assert!(a.frames == vec![3, 2]);
+        assert!(tag_animation(&tag(3, 1, LoopDirection::Forward), 4).is_none());
+        assert!(tag_animation(&tag(4, 5, LoopDirection::Forward), 4).is_none());
+        assert!(tag_animation(&tag(0, 0, LoopDirection::Forward), 0).is_none());
+    }
+
+    #[test]
+    fn test_frame_at() {
+        let frames = frames(4);
+        let a = tag_animation(&tag(0, 3, LoopDirection::Forward), 4).unwrap();
+        assert!(a.frame_at(&frames, 0.05) == 0);
+        assert!(a.frame_at(&frames, 0.25) == 2);
+        assert!(a.frame_at(&frames, 0.45) == 0);
+        let empty = Animation { frames: Vec::new(), ..a };
+        assert!(empty.frame_at(&frames, 0.25) == 0);
+    }
+}
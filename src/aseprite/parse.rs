@@ -0,0 +1,523 @@
+//! reads the binary .aseprite format,
+//! see <https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md>
+
+use std::path::Path;
+
+use crate::Error;
+use crate::geometry::*;
+use super::{LoopDirection, Slice, SliceKey};
+
+const HEADER_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+const CHUNK_SLICE: u16 = 0x2022;
+
+const LAYER_FLAG_VISIBLE: u16 = 1;
+const LAYER_FLAG_BACKGROUND: u16 = 8;
+const LAYER_FLAG_REFERENCE: u16 = 64;
+const LAYER_TYPE_NORMAL: u16 = 0;
+const HEADER_FLAG_LAYER_OPACITY: u32 = 1;
+const MAX_PALETTE_SIZE: usize = 256;
+
+/// the data of an .aseprite file, before frames are composited
+pub(crate) struct AseFile {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u16,
+    pub transparent_index: u8,
+    pub layer_opacity_valid: bool,
+    pub palette: Vec<[u8; 4]>,
+    pub layers: Vec<AseLayer>,
+    pub frames: Vec<AseFrame>,
+    pub tags: Vec<AseTag>,
+    pub slices: Vec<Slice>,
+}
+
+pub(crate) struct AseLayer {
+    pub flags: u16,
+    pub layer_type: u16,
+    pub child_level: u16,
+    pub opacity: u8,
+}
+
+pub(crate) struct AseFrame {
+    pub duration_ms: u16,
+    pub cels: Vec<AseCel>,
+}
+
+pub(crate) struct AseCel {
+    pub layer: usize,
+    pub x: i32,
+    pub y: i32,
+    pub opacity: u8,
+    pub z_index: i16,
+    pub content: CelContent,
+}
+
+pub(crate) enum CelContent {
+    Image { width: u32, height: u32, pixels: Vec<u8> },
+    Linked(usize),
+}
+
+pub(crate) struct AseTag {
+    pub from: usize,
+    pub to: usize,
+    pub direction: LoopDirection,
+    pub repeat: u16,
+    pub name: String,
+}
+
+impl AseLayer {
+    pub fn is_visible(&self) -> bool {
+        self.flags & LAYER_FLAG_VISIBLE != 0
+    }
+
+    pub fn is_background(&self) -> bool {
+        self.flags & LAYER_FLAG_BACKGROUND != 0
+    }
+
+    /// returns true for layers whose cels are drawn into frames
+    pub fn is_image(&self) -> bool {
+        self.layer_type == LAYER_TYPE_NORMAL && self.flags & LAYER_FLAG_REFERENCE == 0
+    }
+}
+
+impl AseFile {
+    pub fn load(path: &Path) -> Result<AseFile, Error> {
+        let data = std::fs::read(path).map_err(|e| Error::LoadFile(
+            format!("failed to read aseprite file {}: {}", path.to_string_lossy(), e)))?;
+        Self::parse(&data).map_err(|e| Error::LoadFile(
+            format!("failed to parse aseprite file {}: {}", path.to_string_lossy(), e)))
+    }
+
+    pub fn parse(data: &[u8]) -> Result<AseFile, String> {
+        let mut r = Reader::new(data);
+        r.u32()?; // file size
+        if r.u16()? != HEADER_MAGIC {
+            return Err("not an aseprite file, header magic number did not match".to_string());
+        }
+        let frame_count = r.u16()?;
+        let width = r.u16()? as u32;
+        let height = r.u16()? as u32;
+        let depth = r.u16()?;
+        if depth != 32 && depth != 16 && depth != 8 {
+            return Err(format!("unsupported colour depth {}", depth));
+        }
+        let flags = r.u32()?;
+        r.skip(2 + 4 + 4)?; // speed, reserved
+        let transparent_index = r.u8()?;
+        r.skip(3)?;
+        r.seek(128)?; // rest of header is unused
+
+        let mut file = AseFile {
+            width, height, depth, transparent_index,
+            layer_opacity_valid: flags & HEADER_FLAG_LAYER_OPACITY != 0,
+            palette: Vec::new(),
+            layers: Vec::new(),
+            frames: Vec::new(),
+            tags: Vec::new(),
+            slices: Vec::new(),
+        };
+        let mut has_new_palette = false;
+        for _ in 0..frame_count {
+            let frame_start = r.pos;
+            let frame_size = r.u32()? as usize;
+            if r.u16()? != FRAME_MAGIC {
+                return Err("frame magic number did not match".to_string());
+            }
+            let old_chunks = r.u16()? as u32;
+            let duration_ms = r.u16()?;
+            r.skip(2)?;
+            let new_chunks = r.u32()?;
+            let chunk_count = if new_chunks == 0 { old_chunks } else { new_chunks };
+            let mut frame = AseFrame { duration_ms, cels: Vec::new() };
+            for _ in 0..chunk_count {
+                let chunk_start = r.pos;
+                let chunk_size = r.u32()? as usize;
+                let chunk_type = r.u16()?;
+                let mut c = Reader::new(r.bytes(chunk_size.saturating_sub(6))?);
+                match chunk_type {
+                    CHUNK_LAYER => file.layers.push(read_layer(&mut c)?),
+                    CHUNK_CEL => frame.cels.push(read_cel(&mut c, depth)?),
+                    CHUNK_TAGS => file.tags.append(&mut read_tags(&mut c)?),
+                    CHUNK_SLICE => file.slices.push(read_slice(&mut c)?),
+                    CHUNK_PALETTE => {
+                        read_palette(&mut c, &mut file.palette)?;
+                        has_new_palette = true;
+                    },
+                    CHUNK_OLD_PALETTE if !has_new_palette => read_old_palette(&mut c, &mut file.palette)?,
+                    _ => (),
+                }
+                r.seek(chunk_start + chunk_size)?;
+            }
+            file.frames.push(frame);
+            r.seek(frame_start + frame_size)?;
+        }
+        Ok(file)
+    }
+}
+
+fn read_layer(c: &mut Reader) -> Result<AseLayer, String> {
+    let flags = c.u16()?;
+    let layer_type = c.u16()?;
+    let child_level = c.u16()?;
+    c.skip(2 + 2 + 2)?; // default width, height, blend mode
+    let opacity = c.u8()?;
+    c.skip(3)?;
+    c.string()?; // name
+    Ok(AseLayer { flags, layer_type, child_level, opacity })
+}
+
+fn read_cel(c: &mut Reader, depth: u16) -> Result<AseCel, String> {
+    let layer = c.u16()? as usize;
+    let x = c.i16()? as i32;
+    let y = c.i16()? as i32;
+    let opacity = c.u8()?;
+    let cel_type = c.u16()?;
+    let z_index = c.i16()?;
+    c.skip(5)?;
+    let content = match cel_type {
+        0 | 2 => {
+            let width = c.u16()? as u32;
+            let height = c.u16()? as u32;
+            let rest = c.rest();
+            let raw = if cel_type == 0 {
+                rest.to_vec()
+            } else {
+                miniz_oxide::inflate::decompress_to_vec_zlib(rest)
+                    .map_err(|e| format!("failed to decompress cel: {:?}", e))?
+            };
+            let len = width as usize * height as usize * (depth as usize / 8);
+            if raw.len() < len {
+                return Err("cel pixel data was shorter than its size".to_string());
+            }
+            CelContent::Image { width, height, pixels: raw[..len].to_vec() }
+        },
+        1 => CelContent::Linked(c.u16()? as usize),
+        // tilemap cels are not supported, draw nothing for them
+        _ => CelContent::Image { width: 0, height: 0, pixels: Vec::new() },
+    };
+    Ok(AseCel { layer, x, y, opacity, z_index, content })
+}
+
+fn read_tags(c: &mut Reader) -> Result<Vec<AseTag>, String> {
+    let count = c.u16()?;
+    c.skip(8)?;
+    let mut tags = Vec::new();
+    for _ in 0..count {
+        let from = c.u16()? as usize;
+        let to = c.u16()? as usize;
+        let direction = match c.u8()? {
+            1 => LoopDirection::Reverse,
+            2 => LoopDirection::PingPong,
+            3 => LoopDirection::PingPongReverse,
+            _ => LoopDirection::Forward,
+        };
+        let repeat = c.u16()?;
+        c.skip(6 + 3 + 1)?; // reserved, deprecated colour, extra byte
+        let name = c.string()?;
+        tags.push(AseTag { from, to, direction, repeat, name });
+    }
+    Ok(tags)
+}
+
+fn read_palette(c: &mut Reader, palette: &mut Vec<[u8; 4]>) -> Result<(), String> {
+    let size = c.u32()? as usize;
+    let first = c.u32()? as usize;
+    let last = c.u32()? as usize;
+    c.skip(8)?;
+    if first > last {
+        return Err(format!("palette entries {} to {} are out of order", first, last));
+    }
+    let size = size.min(MAX_PALETTE_SIZE);
+    if palette.len() < size {
+        palette.resize(size, [0, 0, 0, 0]);
+    }
+    for i in first..=last {
+        let flags = c.u16()?;
+        let colour = [c.u8()?, c.u8()?, c.u8()?, c.u8()?];
+        if flags & 1 != 0 {
+            c.string()?;
+        }
+        // only indexed pixels use the palette, and they can't reach entries past 255
+        if i >= MAX_PALETTE_SIZE {
+            continue;
+        }
+        if i >= palette.len() {
+            palette.resize(i + 1, [0, 0, 0, 0]);
+        }
+        palette[i] = colour;
+    }
+    Ok(())
+}
+
+fn read_old_palette(c: &mut Reader, palette: &mut Vec<[u8; 4]>) -> Result<(), String> {
+    let packets = c.u16()?;
+    let mut index = 0;
+    for _ in 0..packets {
+        index += c.u8()? as usize;
+        let count = match c.u8()? { 0 => 256, n => n as usize };
+        for _ in 0..count {
+            let colour = [c.u8()?, c.u8()?, c.u8()?, 255];
+            if index < MAX_PALETTE_SIZE {
+                if index >= palette.len() {
+                    palette.resize(index + 1, [0, 0, 0, 0]);
+                }
+                palette[index] = colour;
+            }
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+fn read_slice(c: &mut Reader) -> Result<Slice, String> {
+    let key_count = c.u32()?;
+    let flags = c.u32()?;
+    c.skip(4)?;
+    let name = c.string()?;
+    let mut keys = Vec::new();
+    for _ in 0..key_count {
+        let frame = c.u32()? as usize;
+        let rect = c.rect()?;
+        let centre = if flags & 1 != 0 { Some(c.rect()?) } else { None };
+        let pivot = if flags & 2 != 0 {
+            Some(Vec2::new(c.i32()? as f64, c.i32()? as f64))
+        } else {
+            None
+        };
+        keys.push(SliceKey { frame, rect, centre, pivot });
+    }
+    Ok(Slice { name, keys })
+}
+
+/// reads little endian values from a byte slice
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        if self.pos + count > self.data.len() {
+            return Err("unexpected end of file".to_string());
+        }
+        let bytes = &self.data[self.pos..self.pos + count];
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let bytes = &self.data[self.pos..];
+        self.pos = self.data.len();
+        bytes
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), String> {
+        self.bytes(count).map(|_| ())
+    }
+
+    fn seek(&mut self, pos: usize) -> Result<(), String> {
+        if pos > self.data.len() {
+            return Err("unexpected end of file".to_string());
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).to_string())
+    }
+
+    fn rect(&mut self) -> Result<Rect, String> {
+        Ok(Rect::new(
+            self.i32()? as f64,
+            self.i32()? as f64,
+            self.u32()? as f64,
+            self.u32()? as f64,
+        ))
+    }
+}
+
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(format!("resources/textures/aseprite/{}.aseprite", name)).unwrap()
+    }
+
+    fn string(out: &mut Vec<u8>, s: &str) {
+        out.extend((s.len() as u16).to_le_bytes());
+        out.extend(s.as_bytes());
+    }
+
+    fn chunk(chunk_type: u16, data: &[u8]) -> Vec<u8> {
+        let mut out = ((data.len() + 6) as u32).to_le_bytes().to_vec();
+        out.extend(chunk_type.to_le_bytes());
+        out.extend(data);
+        out
+    }
+
+    /// a 32 bit file with the chunks in its first frame and empty frames after
+    fn ase_file(frame_count: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = vec![0; 4];
+        out.extend(HEADER_MAGIC.to_le_bytes());
+        out.extend(frame_count.to_le_bytes());
+        out.extend(16u16.to_le_bytes());
+        out.extend(16u16.to_le_bytes());
+        out.extend(32u16.to_le_bytes());
+        out.resize(128, 0);
+        for i in 0..frame_count {
+            let chunks: &[Vec<u8>] = if i == 0 { chunks } else { &[] };
+            let body: Vec<u8> = chunks.concat();
+            out.extend(((body.len() + 16) as u32).to_le_bytes());
+            out.extend(FRAME_MAGIC.to_le_bytes());
+            out.extend((chunks.len() as u16).to_le_bytes());
+            out.extend(100u16.to_le_bytes());
+            out.extend([0; 2]);
+            out.extend((chunks.len() as u32).to_le_bytes());
+            out.extend(body);
+        }
+        out
+    }
+
+    fn tags_chunk(tags: &[(u16, u16, u8, &str)]) -> Vec<u8> {
+        let mut data = (tags.len() as u16).to_le_bytes().to_vec();
+        data.extend([0; 8]);
+        for (from, to, direction, name) in tags {
+            data.extend(from.to_le_bytes());
+            data.extend(to.to_le_bytes());
+            data.push(*direction);
+            data.extend(0u16.to_le_bytes());
+            data.extend([0; 10]);
+            string(&mut data, name);
+        }
+        chunk(CHUNK_TAGS, &data)
+    }
+
+    fn palette_chunk(size: u32, first: u32, last: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        for v in [size, first, last] {
+            data.extend(v.to_le_bytes());
+        }
+        data.extend([0; 8]);
+        for _ in first..=last {
+            data.extend([0, 0, 1, 2, 3, 255]);
+        }
+        chunk(CHUNK_PALETTE, &data)
+    }
+
+    #[test]
+    fn test_parse_fixtures() {
+        for name in ["button", "microcontroller"] {
+            let file = AseFile::parse(&fixture(name)).unwrap();
+            assert!(file.frames.len() == 1);
+            assert!(file.layers.len() == 1);
+            assert!(file.depth == 32);
+            assert!(file.tags.is_empty());
+            assert!(file.slices.is_empty());
+        }
+        let button = AseFile::parse(&fixture("button")).unwrap();
+        assert!(button.width == 300 && button.height == 200);
+        assert!(button.frames[0].duration_ms == 100);
+        let micro = AseFile::parse(&fixture("microcontroller")).unwrap();
+        assert!(micro.width == 100 && micro.height == 100);
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let data = fixture("button");
+        for len in [0, 10, 127, 130, data.len() - 1] {
+            assert!(AseFile::parse(&data[..len]).is_err());
+        }
+        assert!(AseFile::parse(&[0; 200]).is_err());
+    }
+
+    #[test]
+    fn test_tags() {
+        let data = ase_file(4, &[tags_chunk(&[(0, 3, 0, "walk"), (1, 2, 2, "idle")])]);
+        let file = AseFile::parse(&data).unwrap();
+        assert!(file.frames.len() == 4);
+        assert!(file.tags.len() == 2);
+        assert!(file.tags[0].name == "walk" && file.tags[0].from == 0 && file.tags[0].to == 3);
+        assert!(file.tags[0].direction == LoopDirection::Forward);
+        assert!(file.tags[1].name == "idle" && file.tags[1].from == 1 && file.tags[1].to == 2);
+        assert!(file.tags[1].direction == LoopDirection::PingPong);
+    }
+
+    #[test]
+    fn test_slice_keys() {
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend(2u32.to_le_bytes()); // has pivot
+        data.extend([0; 4]);
+        string(&mut data, "hitbox");
+        for (frame, x) in [(0u32, 1i32), (2, 5)] {
+            data.extend(frame.to_le_bytes());
+            data.extend(x.to_le_bytes());
+            data.extend(2i32.to_le_bytes());
+            data.extend(8u32.to_le_bytes());
+            data.extend(4u32.to_le_bytes());
+            data.extend(3i32.to_le_bytes());
+            data.extend((-1i32).to_le_bytes());
+        }
+        let file = AseFile::parse(&ase_file(3, &[chunk(CHUNK_SLICE, &data)])).unwrap();
+        let slice = &file.slices[0];
+        assert!(slice.name == "hitbox");
+        assert!(slice.keys.len() == 2);
+        assert!(slice.keys[1].frame == 2);
+        let r = slice.keys[1].rect;
+        assert!(r.x == 5.0 && r.y == 2.0 && r.w == 8.0 && r.h == 4.0);
+        assert!(slice.keys[0].centre.is_none());
+        assert!(slice.keys[0].pivot == Some(Vec2::new(3.0, -1.0)));
+        assert!(slice.key_at(1).unwrap().frame == 0);
+        assert!(slice.key_at(2).unwrap().frame == 2);
+    }
+
+    #[test]
+    fn test_palette_range() {
+        let file = AseFile::parse(&ase_file(1, &[palette_chunk(4, 0, 3)])).unwrap();
+        assert!(file.palette.len() == 4);
+        assert!(file.palette[3] == [1, 2, 3, 255]);
+        let file = AseFile::parse(&ase_file(1, &[palette_chunk(100000, 0, 1)])).unwrap();
+        assert!(file.palette.len() == MAX_PALETTE_SIZE);
+        assert!(AseFile::parse(&ase_file(1, &[palette_chunk(4, 3, 1)])).is_err());
+        // rgb files can have bigger palettes, the entries past 255 are skipped
+        let file = AseFile::parse(&ase_file(1, &[palette_chunk(300, 250, 300)])).unwrap();
+        assert!(file.palette.len() == MAX_PALETTE_SIZE);
+        assert!(file.palette[255] == [1, 2, 3, 255]);
+    }
+}
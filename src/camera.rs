@@ -8,7 +8,7 @@ use crate::resources::{
     resource,
     types::TextObject,
 };
-use crate::Colour;
+use crate::{Colour, TextEffects};
 
 
 pub(crate) enum Draw {
@@ -81,14 +81,25 @@ impl Camera {
 
     /// Draws a disposable text texture adjusted for the camera's `view`
     pub fn draw_disposable_text(&mut self, font: &resource::Font, text: String, height: u32, pos: Vec2, colour: Colour, parallax: Vec2) {
+        self.draw_disposable_text_with_effects(font, text, height, pos, colour, TextEffects::default(), parallax);
+    }
+
+    /// Draws a disposable text texture with a shadow or outline adjusted for the camera's `view`
+    ///
+    /// The effects are drawn as copies of the text each frame, and an outline takes
+    /// at least 8 copies, more for thicker outlines. Text that doesn't change every frame
+    /// is cheaper as a [TextObject] with [crate::manager::FontManager::set_text_effects],
+    /// which renders the effects into its texture once.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_disposable_text_with_effects(&mut self, font: &resource::Font, text: String, height: u32, pos: Vec2, colour: Colour, effects: TextEffects, parallax: Vec2) {
         let rect = self.rect_to_cam_space(Rect::new(pos.x, pos.y, height as f64, height as f64), parallax);
         self.draws.push(Draw::DisposableText(DisposableTextDraw {
             font: *font,
             text,
-            height: rect.h as u32,
             pos: rect.top_left(),
             colour,
             rect,
+            effects,
         }))
     }
 
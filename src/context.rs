@@ -87,7 +87,7 @@ impl DrawingArea {
     pub fn new(window_name: &str, cam_rect: Rect, window_size: Vec2) -> Result<(Camera, DrawingArea,ContextSdl), Error> {
         let cam = Camera::new(cam_rect, window_size);
         let (mut canvas, holder) = ContextSdl::new(&cam, window_name)?;
-        println!("SDL2 context loaded...");
+        log::info!("SDL2 context loaded");
         canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
         Ok((cam, DrawingArea { canvas }, holder))
     }
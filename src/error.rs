@@ -1,6 +1,9 @@
 
 /// Used by the library for reporting errors, so that the calling program can respond appropriately
+///
+/// New kinds of error may be added, so matches on it need a wildcard arm
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Some part of sdl failed to initilaize, this error is usually unrecoverable and indicates an issue with the environment or with missing library files
     Sdl2InitFailure(String),
@@ -12,6 +15,9 @@ pub enum Error {
     Draw(String),
     /// Occurs if there was a problem creating a texture from a font and a string
     TextRender(String),
+    /// Occurs if a texture could not be created or updated from a pixel buffer,
+    /// ie from 'TextureManager::create_texture' or 'TextureManager::update_texture'
+    TextureCreate(String),
     /// Occurs if a resource that has been freed is used
     MissingResource(String),
     /// Occurs if an audio resource fails to play
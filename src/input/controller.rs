@@ -52,29 +52,29 @@ impl ControllerHandler {
                         Ok(gc) => {
                             let id = gc.instance_id();
                             self.sdl_controllers.insert(id, gc);
-                            println!("controller added: {}", id);
+                            log::info!("controller added: {}", id);
                             self.controllers.push(Controller::new(id));
                         },
-                        Err(e) => eprintln!("error opening controller {:?}", e),
+                        Err(e) => log::error!("error opening controller {:?}", e),
                     }
                 } else {
-                    eprintln!("warning: added device was not a game controller, unsupported");
+                    log::warn!("added device was not a game controller, unsupported");
                 }
             },
             
             Event::ControllerDeviceRemoved { which , .. } => {
-                println!("controller removed: {}", which);
+                log::info!("controller removed: {}", which);
                 self.sdl_controllers.remove(which);
                 self.controllers.retain(|c| c.id != *which);
             },
             
-            Event::ControllerAxisMotion { /*which, axis, value,*/ .. } => {
-                //println!("id: {} axis: {:?} value: {}", which, axis, value);
+            Event::ControllerAxisMotion { which, axis, value, .. } => {
+                log::trace!("id: {} axis: {:?} value: {}", which, axis, value);
                 self.input_changed = true;
             }
             
             Event::ControllerButtonDown { which, button, .. } => {
-                //println!("down:  id: {} button: {:?}", which, button);
+                log::trace!("down:  id: {} button: {:?}", which, button);
                 self.input_changed = true;
                 for c in self.controllers.iter_mut() {
                     if c.id == *which {
@@ -84,7 +84,7 @@ impl ControllerHandler {
             }
             
             Event::ControllerButtonUp { which, button, .. } => {
-                //println!("up:  id: {} button: {:?}", which, button);
+                log::trace!("up:  id: {} button: {:?}", which, button);
                 self.input_changed = true;
                 for c in self.controllers.iter_mut() {
                     if c.id == *which {
@@ -1,10 +1,16 @@
 //! A library to abstract away the details of the sdl2 library for creating games easier
+//!
+//! Diagnostics, such as resources being loaded and unloaded or controllers being connected,
+//! are reported through the [log](https://crates.io/crates/log) crate.
+//! Nothing is printed unless the game sets up a logger,
+//! and the level can be limited with `log::set_max_level` or the logger's own filters.
 
 pub mod input;
 mod error;
 mod resources;
 mod render;
 pub mod map;
+pub mod aseprite;
 mod camera;
 mod rect_conversion;
 mod error_macros;
@@ -22,5 +28,13 @@ pub mod manager {
     pub use super::audio::{MusicManager, SfxManager};
 }
 pub use resources::types::{Colour, GameObject, TextObject};
+pub use resources::pixels::{Pixels, CollisionMask};
+pub use resources::palette::Palette;
+pub use resources::stats::{ResourceStats, ResourceInfo};
+pub use resources::text_layout::{TextLayout, HorizontalAlign, VerticalAlign};
+pub use resources::text_metrics::TextMetrics;
+pub use resources::font_settings::{FontSettings, FontHinting};
+pub use resources::rich_text::{TextSpan, parse_markup};
+pub use resources::text_effects::{TextEffects, TextShadow, TextOutline};
 pub use camera::Camera;
 pub use nze_geometry as geometry;
@@ -1,5 +1,6 @@
 use std::path::Path;
 use crate::{GameObject, TextObject, Colour, resource, manager::FontManager, Camera, Error};
+use crate::{TextLayout, HorizontalAlign, VerticalAlign, FontSettings};
 use super::tile::*;
 use crate::geometry::*;
 use super::tiled;
@@ -83,30 +84,47 @@ impl Layer {
             l.info.colour.a as u8
             );
         for t in l.text.iter() {
-            let font = font_manager.load_font(
+            let font = font_manager.load_font_with(
                 &font_folder.join(
                     Path::new(&(t.font_family.replace(" ", "-") + ".ttf"))
-                )
+                ),
+                &FontSettings { bold: t.bold, italic: t.italic, ..Default::default() }
             )?;
 
-            layer.text_draw.push(
-                font_manager.load_text_obj(
-                    &font,
-                    &t.text,
-                    layer_colour, //use layer colour to generate text texture
-                    t.obj.rect.top_left(),
-                    t.obj.rect.h,
-                    l.info.parallax
-                )?
-            );
-            // change the text draw's colour to the colour of the text object,
+            let mut layout = TextLayout::new(t.obj.rect);
+            layout.wrap = t.wrap;
+            layout.horizontal_align = match t.horizontal_align {
+                tiled::TextHorizontalAlign::Left => HorizontalAlign::Left,
+                tiled::TextHorizontalAlign::Center => HorizontalAlign::Centre,
+                tiled::TextHorizontalAlign::Right => HorizontalAlign::Right,
+                tiled::TextHorizontalAlign::Justify => HorizontalAlign::Justify,
+            };
+            layout.vertical_align = match t.vertical_align {
+                tiled::TextVerticalAlign::Top => VerticalAlign::Top,
+                tiled::TextVerticalAlign::Center => VerticalAlign::Centre,
+                tiled::TextVerticalAlign::Bottom => VerticalAlign::Bottom,
+            };
+            let line_height = font_manager.line_height(&font, t.pixel_size as f64)?;
+            let text_objs = font_manager.load_text_layout(
+                &font,
+                &t.text,
+                layer_colour, //use layer colour to generate text texture
+                line_height,
+                &layout,
+                l.info.parallax
+            )?;
+            // change the text draws' colour to the colour of the text object,
             // so that the layer and text object colours are mixed
-            layer.text_draw.last_mut().unwrap().colour = Colour::new(
-                t.colour.r as u8,
-                t.colour.g as u8,
-                t.colour.b as u8,
-                t.colour.a as u8
+            let text_colour = Colour::new(
+                t.colour.r,
+                t.colour.g,
+                t.colour.b,
+                t.colour.a
             );
+            for mut obj in text_objs {
+                obj.colour = text_colour;
+                layer.text_draw.push(obj);
+            }
         }
         Ok(layer)
     }
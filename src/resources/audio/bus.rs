@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use sdl2::mixer;
+
+
+
+/// A group of sounds that can have their volume changed, be muted, paused or stopped together
+///
+/// Music plays on the `Music` bus, and sound effects play on the `Sfx` bus
+/// unless they are moved to another with [SfxManager::set_bus](super::SfxManager::set_bus).
+/// Every bus is part of the `Master` bus.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Bus {
+    Master,
+    Music,
+    Sfx,
+    Voice,
+    Ui,
+    /// a group for any other sounds, ie ambience or a character's footsteps
+    Custom(u16),
+}
+
+impl Bus {
+    /// the sdl_mixer channel group tag of the bus
+    pub(super) fn group(&self) -> mixer::Group {
+        mixer::Group(match self {
+            Bus::Master => -1,
+            Bus::Music => 1,
+            Bus::Sfx => 2,
+            Bus::Voice => 3,
+            Bus::Ui => 4,
+            Bus::Custom(n) => 16 + *n as i32,
+        })
+    }
+
+    /// true if sounds on the other bus are part of this bus
+    pub(super) fn contains(&self, other: Bus) -> bool {
+        *self == Bus::Master || *self == other
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct BusSettings {
+    pub(super) volume: f64,
+    pub(super) muted: bool,
+    /// a change of volume in progress
+    pub(super) fade: Option<VolumeFade>,
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        BusSettings { volume: 1.0, muted: false, fade: None }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct VolumeFade {
+    pub(super) from: f64,
+    pub(super) to: f64,
+    pub(super) start: Instant,
+    pub(super) seconds: f64,
+}
+
+/// A mixing state, made of volumes for some of the buses,
+/// ie quieter music and sound effects while a pause menu is open
+///
+/// Buses the snapshot has no volume for are left as they are when it is applied,
+/// see [AudioManager::add_snapshot](super::AudioManager::add_snapshot)
+///
+/// Snapshots only hold volumes, sdl_mixer has no filters,
+/// so effects like muffling the music under water are not part of a snapshot.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MixSnapshot {
+    pub volumes: HashMap<Bus, f64>,
+}
+
+impl MixSnapshot {
+    pub fn new() -> MixSnapshot {
+        MixSnapshot::default()
+    }
+
+    pub fn with_volume(mut self, bus: Bus, volume: f64) -> MixSnapshot {
+        self.volumes.insert(bus, volume);
+        self
+    }
+}
+
+/// How a bus is quietened while another bus is playing,
+/// ie music while dialogue is spoken, see [AudioManager::set_ducking](super::AudioManager::set_ducking)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ducking {
+    /// what the bus volume is multiplied by while ducked
+    pub volume: f64,
+    /// seconds to quieten the bus after the other bus starts playing
+    pub attack: f64,
+    /// seconds to return to full volume after the other bus stops playing
+    pub release: f64,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Ducking { volume: 0.3, attack: 0.1, release: 0.5 }
+    }
+}
+
+/// a bus being ducked by another
+pub(super) struct DuckRule {
+    pub(super) bus: Bus,
+    pub(super) trigger: Bus,
+    pub(super) ducking: Ducking,
+    /// the current volume multiplier from ducking, `1` when not ducked
+    pub(super) gain: f64,
+}
@@ -0,0 +1,301 @@
+//! Used for playing music and sound effects
+//!
+//! Make an `AudioManager` to inititalize sdl2_mixer.
+//! `AudioManager` holds a struct for controlling music, and has functions for sound effect loading and playback.
+//! The volume of groups of sounds is controlled with [Bus]es on the `AudioManager`.
+//! Bus volumes can be faded between [MixSnapshot]s and ducked while other buses play.
+//! `AudioManager::update` must be called each frame to move fades, ducking and playlists along.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use sdl2::mixer;
+use crate::{Error, init_err, resource::Music};
+
+macro_rules! audio_load {
+    (
+        $(#[$($attrss:tt)*])*,                       // doc comments
+        $fn_name:ident($self:ident) -> $res_type:tt, // , fn_name(self) -> resource_type, 
+        $res_list:expr, $res_map: expr, $name:expr,  // list_of_resources, map_of_resource_filepaths, "Resource Name",
+        $load_cmd:expr                               // file_loading_function 
+    ) => {
+        $(#[$($attrss)*])*
+        pub fn $fn_name(&mut $self, filepath: &Path) -> Result<$res_type, Error> {
+            let index = $crate::load_resource!(
+                filepath, $res_list, $res_map, $name, Some($crate::file_err!($load_cmd(filepath))?));
+            Ok($res_type{ id: index })
+        }
+    };
+}
+
+mod bus;
+mod sfx;
+mod music;
+mod playlist;
+mod music_loop;
+mod pcm;
+mod synth;
+
+pub use bus::{Bus, MixSnapshot, Ducking};
+pub use sfx::{SfxManager, VoiceLimits};
+pub use music::{MusicManager, LoopPoints};
+pub use playlist::Playlist;
+pub use synth::{SynthSound, Waveform};
+
+use bus::{BusSettings, VolumeFade, DuckRule};
+
+/// Holds audio resources and controls playback of audio
+///
+/// There are multiple sound effect channels, and one
+/// channel for music playback, with a second reserved for crossfading music.
+pub struct AudioManager<'a> {
+    pub music: MusicManager<'a>,
+    pub sfx: SfxManager,
+    buses: HashMap<Bus, BusSettings>,
+    snapshots: HashMap<String, MixSnapshot>,
+    /// the name of the snapshot last applied
+    snapshot: Option<String>,
+    ducking: Vec<DuckRule>,
+    last_update: Instant,
+    _mixer_context: mixer::Sdl2MixerContext,
+}
+
+impl<'a> AudioManager<'a> {
+    /// create a new AudioManager
+    ///
+    /// this starts the sdl_mixer context
+    pub fn new() -> Result<AudioManager<'a>, Error> {
+        init_err!(mixer::open_audio(
+            mixer::DEFAULT_FREQUENCY,
+            mixer::DEFAULT_FORMAT,
+            mixer::DEFAULT_CHANNELS,
+            512, // between 256 and 1024 recommened, lower number = lower latency but not as compatible
+        ))?;
+
+        let _mixer_context = init_err!(mixer::init(mixer::InitFlag::all()))?;
+
+        Ok(AudioManager {
+            _mixer_context,
+            sfx: SfxManager::new(),
+            music : MusicManager::new(),
+            buses: HashMap::new(),
+            snapshots: HashMap::new(),
+            snapshot: None,
+            ducking: Vec::new(),
+            last_update: Instant::now(),
+        })
+    }
+
+    /// Move snapshot transitions and bus fades along, duck buses,
+    /// and start the next track of a playlist
+    ///
+    /// Call this once a frame, it returns the music that finished since the last call.
+    /// Music that was stopped with [MusicManager::stop] or replaced by playing other music
+    /// is not counted as finished, but music that ended by fading out is.
+    pub fn update(&mut self) -> Result<Option<Music>, Error> {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_update).as_secs_f64();
+        self.last_update = now;
+        let mut changed: Vec<Bus> = Vec::new();
+        for (bus, settings) in self.buses.iter_mut() {
+            if let Some(fade) = settings.fade {
+                let t = (now.duration_since(fade.start).as_secs_f64() / fade.seconds).min(1.0);
+                settings.volume = fade.from + (fade.to - fade.from) * t;
+                if t >= 1.0 {
+                    settings.fade = None;
+                }
+                changed.push(*bus);
+            }
+        }
+        for i in 0..self.ducking.len() {
+            let rule = &self.ducking[i];
+            let (target, seconds) = match self.bus_playing(rule.trigger) {
+                true => (rule.ducking.volume.clamp(0.0, 1.0), rule.ducking.attack),
+                false => (1.0, rule.ducking.release),
+            };
+            if rule.gain == target {
+                continue;
+            }
+            let step = match seconds > 0.0 {
+                true => (1.0 - rule.ducking.volume.clamp(0.0, 1.0)).max(f64::EPSILON) * dt / seconds,
+                false => f64::INFINITY,
+            };
+            let rule = &mut self.ducking[i];
+            rule.gain = match rule.gain < target {
+                true => (rule.gain + step).min(target),
+                false => (rule.gain - step).max(target),
+            };
+            changed.push(rule.bus);
+        }
+        for bus in changed {
+            self.apply_bus(bus);
+        }
+        self.music.update()
+    }
+
+    /// Change the volume of the bus gradually over `seconds`,
+    /// as [AudioManager::update] is called
+    pub fn fade_bus_volume(&mut self, bus: Bus, volume: f64, seconds: f64) {
+        if seconds <= 0.0 {
+            return self.set_bus_volume(bus, volume);
+        }
+        let settings = self.bus_settings(bus);
+        settings.fade = Some(VolumeFade {
+            from: settings.volume,
+            to: volume.clamp(0.0, 1.0),
+            start: Instant::now(),
+            seconds,
+        });
+    }
+
+    /// Save a set of bus volumes with a name, to be applied later with [AudioManager::apply_snapshot]
+    ///
+    /// replaces any snapshot with the same name
+    pub fn add_snapshot(&mut self, name: &str, snapshot: MixSnapshot) {
+        self.snapshots.insert(name.to_string(), snapshot);
+    }
+
+    pub fn remove_snapshot(&mut self, name: &str) {
+        self.snapshots.remove(name);
+    }
+
+    /// Fade the buses to the volumes of the named snapshot over `seconds`
+    ///
+    /// returns an error if there is no snapshot with that name
+    pub fn apply_snapshot(&mut self, name: &str, seconds: f64) -> Result<(), Error> {
+        let snapshot = match self.snapshots.get(name) {
+            Some(s) => s.clone(),
+            None => return Err(Error::MissingResource(format!("no mix snapshot named {}", name))),
+        };
+        for (bus, volume) in snapshot.volumes {
+            self.fade_bus_volume(bus, volume, seconds);
+        }
+        self.snapshot = Some(name.to_string());
+        Ok(())
+    }
+
+    /// The name of the snapshot last applied
+    pub fn current_snapshot(&self) -> Option<&str> {
+        self.snapshot.as_deref()
+    }
+
+    /// Quieten the bus automatically while anything on the `while_playing` bus is playing
+    ///
+    /// The ducking is mixed with the bus volume, so snapshots and fades still apply,
+    /// and it is updated by [AudioManager::update].
+    /// Replaces any ducking of the bus by the same other bus.
+    pub fn set_ducking(&mut self, bus: Bus, while_playing: Bus, ducking: Ducking) {
+        match self.ducking.iter_mut().find(|r| r.bus == bus && r.trigger == while_playing) {
+            Some(rule) => rule.ducking = ducking,
+            None => self.ducking.push(DuckRule { bus, trigger: while_playing, ducking, gain: 1.0 }),
+        }
+    }
+
+    /// Stop ducking the bus while the other bus is playing
+    pub fn clear_ducking(&mut self, bus: Bus, while_playing: Bus) {
+        self.ducking.retain(|r| !(r.bus == bus && r.trigger == while_playing));
+        self.apply_bus(bus);
+    }
+
+    /// true if any unpaused sound on the bus is playing
+    fn bus_playing(&self, bus: Bus) -> bool {
+        (bus.contains(Bus::Music) && self.music.playing() && !self.music.paused())
+            || self.sfx.bus_playing(bus)
+    }
+
+    /// Set the volume of every sound on the bus,
+    /// which is mixed with the volumes of the sounds and the `Master` bus
+    ///
+    /// the range of volume values is `0.0` to `1.0`
+    pub fn set_bus_volume(&mut self, bus: Bus, volume: f64) {
+        let settings = self.bus_settings(bus);
+        settings.volume = volume.clamp(0.0, 1.0);
+        settings.fade = None;
+        self.apply_bus(bus);
+    }
+
+    /// Get the volume of the bus, buses start at `1.0`
+    ///
+    /// during a fade this is the volume part way through, and it doesn't include ducking
+    pub fn get_bus_volume(&self, bus: Bus) -> f64 {
+        self.buses.get(&bus).map_or(1.0, |b| b.volume)
+    }
+
+    /// Silence the bus without changing its volume
+    pub fn set_bus_muted(&mut self, bus: Bus, muted: bool) {
+        self.bus_settings(bus).muted = muted;
+        self.apply_bus(bus);
+    }
+
+    pub fn is_bus_muted(&self, bus: Bus) -> bool {
+        self.buses.get(&bus).is_some_and(|b| b.muted)
+    }
+
+    /// Pause every sound playing on the bus
+    pub fn pause_bus(&mut self, bus: Bus) {
+        if bus.contains(Bus::Music) {
+            self.music.pause();
+        }
+        self.sfx.pause_bus(bus);
+    }
+
+    /// Resume every paused sound on the bus
+    pub fn resume_bus(&mut self, bus: Bus) {
+        if bus.contains(Bus::Music) {
+            self.music.resume();
+        }
+        self.sfx.resume_bus(bus);
+    }
+
+    /// Stop every sound playing on the bus
+    pub fn stop_bus(&mut self, bus: Bus) {
+        if bus.contains(Bus::Music) {
+            self.music.stop();
+        }
+        self.sfx.stop_bus(bus);
+    }
+
+    /// Pause all music and sound effects, ie when opening a pause menu
+    pub fn pause_all(&mut self) {
+        self.pause_bus(Bus::Master);
+    }
+
+    /// Resume all paused music and sound effects
+    pub fn resume_all(&mut self) {
+        self.resume_bus(Bus::Master);
+    }
+
+    /// Stop all music and sound effects
+    pub fn stop_all(&mut self) {
+        self.stop_bus(Bus::Master);
+    }
+
+    fn bus_settings(&mut self, bus: Bus) -> &mut BusSettings {
+        self.buses.entry(bus).or_default()
+    }
+
+    /// the volume of the bus alone with its ducking, or `0` if it is muted
+    fn bus_gain(&self, bus: Bus) -> f64 {
+        let duck = self.ducking.iter()
+            .filter(|r| r.bus == bus)
+            .map(|r| r.gain)
+            .fold(1.0, f64::min);
+        match self.buses.get(&bus) {
+            Some(b) if b.muted => 0.0,
+            Some(b) => b.volume * duck,
+            None => duck,
+        }
+    }
+
+    /// update the volumes of the sounds playing on the bus
+    fn apply_bus(&mut self, bus: Bus) {
+        self.sfx.set_bus_gain(bus, self.bus_gain(bus));
+        if bus.contains(Bus::Music) {
+            self.music.set_bus_gain(self.bus_gain(Bus::Master) * self.bus_gain(Bus::Music));
+        }
+    }
+}
+
+/// seconds to the milliseconds used by sdl_mixer
+fn to_ms(seconds: f64) -> i32 {
+    (seconds.max(0.0) * 1000.0) as i32
+}
@@ -0,0 +1,576 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use sdl2::mixer;
+use crate::{
+    Error,
+    resource::Music,
+    use_resource,
+    unload_resource,
+    resources::stats::{ResourceStats, file_size},
+};
+use super::{Bus, Playlist, to_ms};
+use super::playlist::PlaylistState;
+use super::pcm::{MixerSpec, chunk_from_samples};
+use super::music_loop::{LoopPlayer, ogg_loop_tags};
+
+/// the sound effect channel reserved for the second layer of music when crossfading
+pub(super) const MUSIC_LAYER: mixer::Channel = mixer::Channel(0);
+
+/// set by sdl_mixer's callbacks when the music stream or [MUSIC_LAYER] stop,
+/// which run on the audio thread so can't touch the [MusicManager]
+static STREAM_FINISHED: AtomicBool = AtomicBool::new(false);
+static LAYER_FINISHED: AtomicBool = AtomicBool::new(false);
+
+fn on_stream_finished() {
+    STREAM_FINISHED.store(true, Ordering::Release);
+}
+
+/// Where music goes back to after its intro, see [MusicManager::play_with_loop]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoopPoints {
+    /// seconds from the start of the music that each loop starts at
+    pub start: f64,
+    /// seconds from the start of the music that each loop ends at,
+    /// or `None` to loop from the end of the music
+    pub end: Option<f64>,
+}
+
+/// How long the streamed music has played for, as sdl_mixer can't report it
+#[derive(Clone, Copy)]
+struct MusicClock {
+    started: Instant,
+    /// the position the music started from
+    offset: f64,
+    paused_at: Option<Instant>,
+}
+
+impl MusicClock {
+    fn new(offset: f64) -> MusicClock {
+        MusicClock { started: Instant::now(), offset, paused_at: None }
+    }
+
+    fn seconds(&self) -> f64 {
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        self.offset + now.duration_since(self.started).as_secs_f64()
+    }
+
+    fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+        }
+    }
+
+    fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.started += paused_at.elapsed();
+        }
+    }
+}
+
+/// Load and play [Music], created and owned by [AudioManager](super::AudioManager)
+///
+/// Music is streamed with sdl_mixer's music channel, but as there is only one,
+/// `crossfade` plays the incoming music from memory on a reserved sound effect channel.
+/// Later crossfades switch back and forth between the two.
+/// Music with loop points is also played from memory on that channel.
+pub struct MusicManager<'a> {
+    music : Vec<Option<mixer::Music<'a>>>,
+    music_paths: HashMap<String, usize>,
+    /// the music decoded into memory, only for music that has been played from memory
+    music_pcm: Vec<Option<Arc<[u8]>>>,
+    /// the length of music in seconds, only for music that a playlist has faded out
+    music_lengths: Vec<Option<f64>>,
+    /// true when the current music is playing on the [MUSIC_LAYER] channel
+    on_layer: bool,
+    /// the music last played on the [MUSIC_LAYER] channel
+    layer: Option<LoopPlayer>,
+    /// played on the [MUSIC_LAYER] channel for the music to be written over
+    silence: Option<mixer::Chunk>,
+    /// in cells so that pausing and setting the volume don't need the manager to be mutable
+    clock: Cell<MusicClock>,
+    volume: Cell<f64>,
+    /// the volume of the music and master buses
+    bus_gain: f64,
+    /// the music that was last started, until it finishes or is stopped
+    current: Option<Music>,
+    playlist: Option<PlaylistState>,
+}
+
+impl<'a> MusicManager<'a> {
+    pub(super) fn new() -> MusicManager<'a> {
+        mixer::reserve_channels(1);
+        Bus::Music.group().add_channel(MUSIC_LAYER);
+        mixer::Music::hook_finished(on_stream_finished);
+        mixer::set_channel_finished(|channel| {
+            if channel == MUSIC_LAYER {
+                LAYER_FINISHED.store(true, Ordering::Release);
+            }
+        });
+        MusicManager {
+            music: Vec::new(),
+            music_paths: HashMap::new(),
+            music_pcm: Vec::new(),
+            music_lengths: Vec::new(),
+            on_layer: false,
+            layer: None,
+            silence: None,
+            clock: Cell::new(MusicClock::new(0.0)),
+            volume: Cell::new(1.0),
+            bus_gain: 1.0,
+            current: None,
+            playlist: None,
+        }
+    }
+
+    audio_load!(
+        /// load `Music` to memory
+        , load(self) -> Music,
+        self.music, self.music_paths, "Music",
+        sdl2::mixer::Music::from_file
+    );
+
+    /// Play the music file
+    ///
+    /// - the repeat value will loop continously if you pass `-1`
+    /// - volume is on a scale from `0.0` to `1.0`
+    /// - stops any playlist that is playing
+    pub fn play(&mut self, music: Music, repeats: i32) -> Result<(), Error> {
+        self.playlist = None;
+        self.start(music, repeats, 0.0)
+    }
+
+    /// Play the music file, fading in from silence over `seconds`
+    ///
+    /// - the repeat value will loop continously if you pass `-1`
+    /// - stops any playlist that is playing
+    pub fn play_fade_in(&mut self, music: Music, repeats: i32, seconds: f64) -> Result<(), Error> {
+        self.playlist = None;
+        self.start(music, repeats, seconds)
+    }
+
+    /// play the music on the stream, fading in if `seconds` is more than zero
+    fn start(&mut self, music: Music, repeats: i32, seconds: f64) -> Result<(), Error> {
+        self.stop_layer();
+        use_resource!(
+            self.music, music.id,
+            Some(s) => {
+                if seconds > 0.0 {
+                    s.fade_in(repeats, to_ms(seconds)).map_err(
+                        |e| Error::AudioPlay("failed to fade in music, sdl_mixer error: ".to_string() + &e)
+                    )?;
+                } else {
+                    s.play(repeats).map_err(
+                        |e| Error::AudioPlay("failed to play music, sdl_mixer error: ".to_string() + &e)
+                    )?;
+                }
+                Ok(())
+            }
+        )?;
+        self.current = Some(music);
+        self.clock.set(MusicClock::new(0.0));
+        Ok(())
+    }
+
+    /// Play the intro of the music once, then loop between the loop points forever
+    ///
+    /// - stops any playlist that is playing
+    /// - loop points can be read from the music file with [MusicManager::loop_points]
+    ///
+    /// The first time music is played with loop points the whole file is decoded into memory,
+    /// which is kept until the music is unloaded.
+    pub fn play_with_loop(&mut self, music: Music, loop_points: LoopPoints) -> Result<(), Error> {
+        self.playlist = None;
+        mixer::Music::halt();
+        self.play_layer(music, loop_points.start, loop_points.end, -1, 0.0)?;
+        self.current = Some(music);
+        Ok(())
+    }
+
+    /// Get the loop points stored in the music file,
+    /// from `LOOPSTART` and `LOOPLENGTH` or `LOOPEND` comments in an ogg vorbis or opus file
+    ///
+    /// returns `None` if the file has no loop points
+    pub fn loop_points(&self, music: Music) -> Result<Option<LoopPoints>, Error> {
+        let path = self.music_path(music)?;
+        let data = std::fs::read(path).map_err(|e| Error::LoadFile(
+            format!("failed to read music {}: {}", path, e)))?;
+        Ok(ogg_loop_tags(&data).map(|(start, end)| LoopPoints { start, end }))
+    }
+
+    /// Jump to a time in seconds from the start of the current music
+    ///
+    /// - does nothing if no music is playing
+    /// - streamed music must be a format sdl_mixer can seek, such as ogg, mp3 or flac
+    pub fn set_position(&mut self, seconds: f64) -> Result<(), Error> {
+        if self.current.is_none() {
+            return Ok(());
+        }
+        match &self.layer {
+            Some(layer) if self.on_layer => layer.set_position(seconds),
+            _ => {
+                mixer::Music::set_pos(seconds.max(0.0)).map_err(
+                    |e| Error::AudioPlay("failed to set music position, sdl_mixer error: ".to_string() + &e))?;
+                let paused_at = self.clock.get().paused_at.map(|_| Instant::now());
+                self.clock.set(MusicClock { paused_at, ..MusicClock::new(seconds.max(0.0)) });
+            }
+        }
+        Ok(())
+    }
+
+    /// The time in seconds from the start of the current music,
+    /// or `None` if no music is playing
+    ///
+    /// For streamed music this is timed from when the music started,
+    /// so keeps counting up when the music repeats.
+    /// Music played from memory with loop points or by crossfading reports its exact position.
+    pub fn position(&self) -> Option<f64> {
+        self.current?;
+        Some(match &self.layer {
+            Some(layer) if self.on_layer => layer.position(),
+            _ => self.clock.get().seconds(),
+        })
+    }
+
+    /// Play the tracks of the playlist one after another,
+    /// replacing any music or playlist that is playing
+    ///
+    /// [AudioManager::update](super::AudioManager::update) must be called every frame
+    /// to move on to the next track.
+    /// Each track plays once, and the playlist stops after the last track unless it repeats.
+    /// Tracks that fail to play are skipped with a warning in the log.
+    pub fn play_playlist(&mut self, playlist: Playlist) -> Result<(), Error> {
+        self.playlist = Some(PlaylistState::new(playlist));
+        self.start_playlist_track();
+        if self.playlist.is_none() {
+            self.stop();
+        }
+        Ok(())
+    }
+
+    /// Stop moving on to new tracks of the current playlist,
+    /// the current track keeps playing until it finishes
+    pub fn stop_playlist(&mut self) {
+        self.playlist = None;
+    }
+
+    /// Returns true if a playlist is playing, including while waiting between tracks
+    pub fn playlist_playing(&self) -> bool {
+        self.playlist.is_some()
+    }
+
+    /// The music that was last started, until it finishes or is stopped
+    ///
+    /// while crossfading this is the incoming music
+    pub fn current(&self) -> Option<Music> {
+        self.current
+    }
+
+    /// Check for music that has finished playing, and start the next track of the playlist
+    ///
+    /// called by [AudioManager::update](super::AudioManager::update),
+    /// it returns the music that finished since the last call
+    pub(super) fn update(&mut self) -> Result<Option<Music>, Error> {
+        // the layer plays over silence that never ends by itself
+        if self.layer.as_ref().is_some_and(|l| l.ended()) {
+            MUSIC_LAYER.halt();
+            self.layer = None;
+        }
+        let stream_finished = STREAM_FINISHED.swap(false, Ordering::Acquire);
+        let layer_finished = LAYER_FINISHED.swap(false, Ordering::Acquire);
+        // the flags are also set by music fading out of the other layer,
+        // and by halts before new music started, so check the current layer stopped
+        let current_finished = if self.on_layer {
+            layer_finished && !MUSIC_LAYER.is_playing()
+        } else {
+            stream_finished && !mixer::Music::is_playing()
+        };
+        let finished = match current_finished {
+            true => self.current.take(),
+            false => None,
+        };
+        let mut next_track = false;
+        if let Some(state) = &mut self.playlist {
+            if finished.is_some() {
+                state.next_at = Some(Instant::now() + Duration::from_secs_f64(state.playlist.gap.max(0.0)));
+                state.fade_out_at = None;
+            }
+            // playlist tracks are streamed, so the clock has their position
+            if state.fade_out_at.is_some_and(|t| self.clock.get().seconds() >= t) {
+                state.fade_out_at = None;
+                if let Err(e) = mixer::Music::fade_out(to_ms(state.playlist.fade_out)) {
+                    log::warn!("failed to fade out playlist track, sdl_mixer error: {}", e);
+                }
+            }
+            if state.next_at.is_some_and(|t| Instant::now() >= t) {
+                state.next_at = None;
+                next_track = true;
+            }
+        }
+        if next_track {
+            self.start_playlist_track();
+        }
+        Ok(finished)
+    }
+
+    /// start the next track of the playlist, skipping any that fail to play,
+    /// and stop the playlist if there are no more tracks
+    fn start_playlist_track(&mut self) {
+        let mut state = match self.playlist.take() {
+            Some(s) => s,
+            None => return,
+        };
+        // try each track once, in case none of them play
+        for _ in 0..state.order.len() {
+            let track = match state.advance() {
+                Some(t) => t,
+                None => return,
+            };
+            match self.start(track, 1, state.playlist.fade_in) {
+                Ok(()) => {
+                    state.fade_out_at = self.fade_out_start(track, state.playlist.fade_out);
+                    self.playlist = Some(state);
+                    return;
+                },
+                Err(e) => log::warn!("skipped playlist track, id: {} - {:?}", track.id, e),
+            }
+        }
+    }
+
+    /// the position to start fading out the track at so that it is silent when it ends
+    fn fade_out_start(&mut self, track: Music, seconds: f64) -> Option<f64> {
+        if seconds <= 0.0 {
+            return None;
+        }
+        match self.music_length(track) {
+            Ok(length) => Some((length - seconds).max(0.0)),
+            Err(e) => {
+                log::warn!("can't fade out playlist track, id: {} - {:?}", track.id, e);
+                None
+            }
+        }
+    }
+
+    /// Fade the current music out to silence over `seconds`, then stop it
+    ///
+    /// - does nothing if no music is playing
+    /// - stops any playlist that is playing
+    pub fn fade_out(&mut self, seconds: f64) -> Result<(), Error> {
+        self.playlist = None;
+        MUSIC_LAYER.fade_out(to_ms(seconds));
+        mixer::Music::fade_out(to_ms(seconds)).map_err(
+            |e| Error::AudioPlay("failed to fade out music, sdl_mixer error: ".to_string() + &e))
+    }
+
+    /// Fade out the current music while fading in the new music over `seconds`
+    ///
+    /// - the repeat value will loop continously if you pass `-1`
+    /// - if no music is playing, the new music just fades in
+    ///
+    /// The first crossfade to a music decodes the whole file into memory,
+    /// which is kept until the music is unloaded.
+    pub fn crossfade(&mut self, music: Music, repeats: i32, seconds: f64) -> Result<(), Error> {
+        if !self.playing() {
+            return self.play_fade_in(music, repeats, seconds);
+        }
+        self.playlist = None;
+        if self.on_layer {
+            // any music still fading out of the stream from an earlier crossfade
+            // would block the new music from starting
+            mixer::Music::halt();
+            MUSIC_LAYER.fade_out(to_ms(seconds));
+            self.on_layer = false;
+            return use_resource!(
+                self.music, music.id,
+                Some(s) => {
+                    s.fade_in(repeats, to_ms(seconds)).map_err(
+                        |e| Error::AudioPlay("failed to crossfade music, sdl_mixer error: ".to_string() + &e)
+                    )?;
+                    self.current = Some(music);
+                    self.clock.set(MusicClock::new(0.0));
+                    Ok(())
+                }
+            );
+        }
+        // music plays once more than its loop count
+        let loops = if repeats < 0 { -1 } else { (repeats - 1).max(0) };
+        MUSIC_LAYER.set_volume(mixer::Music::get_volume());
+        self.play_layer(music, 0.0, None, loops, seconds)?;
+        mixer::Music::fade_out(to_ms(seconds)).map_err(
+            |e| Error::AudioPlay("failed to crossfade music, sdl_mixer error: ".to_string() + &e))?;
+        self.current = Some(music);
+        Ok(())
+    }
+
+    /// play the music from memory on the [MUSIC_LAYER] channel, fading in over `seconds`
+    fn play_layer(&mut self, music: Music, loop_start: f64, loop_end: Option<f64>, loops: i32, seconds: f64)
+                  -> Result<(), Error> {
+        let pcm = self.get_music_pcm(music)?;
+        let spec = MixerSpec::query()?;
+        if self.silence.is_none() {
+            let samples = vec![0; 1024 * spec.channels as usize];
+            self.silence = Some(chunk_from_samples(&samples, spec.rate, spec.channels)?);
+        }
+        self.layer = Some(LoopPlayer::play(
+            MUSIC_LAYER, self.silence.as_ref().unwrap(), spec, pcm,
+            loop_start, loop_end, loops, to_ms(seconds))?);
+        self.on_layer = true;
+        Ok(())
+    }
+
+    /// get the music decoded into memory, decoding it if this is the first time
+    fn get_music_pcm(&mut self, music: Music) -> Result<Arc<[u8]>, Error> {
+        let path = self.music_path(music)?.to_string();
+        if self.music_pcm.len() <= music.id {
+            self.music_pcm.resize_with(music.id + 1, || None);
+        }
+        if self.music_pcm[music.id].is_none() {
+            let chunk = crate::file_err!(mixer::Chunk::from_file(path))?;
+            // SAFETY: the chunk's buffer is valid until the chunk is dropped
+            let pcm = unsafe { std::slice::from_raw_parts((*chunk.raw).abuf, (*chunk.raw).alen as usize) };
+            self.music_pcm[music.id] = Some(pcm.into());
+        }
+        Ok(self.music_pcm[music.id].clone().unwrap())
+    }
+
+    /// get the length of the music in seconds, decoding it if this is the first time
+    fn music_length(&mut self, music: Music) -> Result<f64, Error> {
+        if let Some(Some(length)) = self.music_lengths.get(music.id) {
+            return Ok(*length);
+        }
+        let spec = MixerSpec::query()?;
+        let bytes = match self.music_pcm.get(music.id) {
+            Some(Some(pcm)) => pcm.len(),
+            _ => {
+                let chunk = crate::file_err!(mixer::Chunk::from_file(self.music_path(music)?))?;
+                // SAFETY: the chunk is valid until it is dropped
+                unsafe { (*chunk.raw).alen as usize }
+            },
+        };
+        let length = bytes as f64 / spec.bytes_per_second();
+        if self.music_lengths.len() <= music.id {
+            self.music_lengths.resize(music.id + 1, None);
+        }
+        self.music_lengths[music.id] = Some(length);
+        Ok(length)
+    }
+
+    fn music_path(&self, music: Music) -> Result<&String, Error> {
+        if !matches!(self.music.get(music.id), Some(Some(_))) {
+            return Err(Error::MissingResource(String::from("Used an unloaded music")));
+        }
+        Ok(self.music_paths.iter().find(|(_, id)| **id == music.id).unwrap().0)
+    }
+
+    /// stop any music playing on the crossfade layer, so the stream is the only music
+    fn stop_layer(&mut self) {
+        if self.on_layer {
+            MUSIC_LAYER.halt();
+            self.on_layer = false;
+            self.layer = None;
+        }
+    }
+
+    /// unloades the internal `Music`
+    pub fn unload(&mut self, music: Music) {
+        if self.current.is_some_and(|c| c.id == music.id) {
+            self.current = None;
+            // music from memory would keep playing after being unloaded
+            self.stop_layer();
+        }
+        if let Some(c) = self.music_pcm.get_mut(music.id) {
+            *c = None;
+        }
+        if let Some(l) = self.music_lengths.get_mut(music.id) {
+            *l = None;
+        }
+        self.unload_stream(music);
+    }
+
+    unload_resource!(
+        , fn unload_stream, self, self.music_paths, self.music, music, Music, "Music");
+
+    /// Get the music currently loaded, with its estimated memory use
+    ///
+    /// The size of music is estimated using the size of its file,
+    /// plus the decoded samples kept for music that has been played as a layer
+    pub fn stats(&self) -> ResourceStats {
+        ResourceStats::from_paths("Music", &self.music_paths, |path, id| {
+            let pcm = match self.music_pcm.get(id) {
+                Some(Some(pcm)) => pcm.len(),
+                _ => 0,
+            };
+            file_size(path) + pcm
+        })
+    }
+
+    /// Returns true if there is music currently playing
+    pub fn playing(&self) -> bool {
+        mixer::Music::is_playing() || MUSIC_LAYER.is_playing()
+    }
+
+    /// Returns true if there is music in the music channel that is paused
+    pub fn paused(&self) -> bool {
+        mixer::Music::is_paused() || MUSIC_LAYER.is_paused()
+    }
+
+    /// set the current volume of the music channel,
+    /// which is mixed with the volume of the music [Bus]
+    ///
+    /// the range of values is `0.0` to `1.0`
+    pub fn set_volume(&self, volume: f64) {
+        self.volume.set(volume);
+        self.apply_volume();
+    }
+
+    /// get the current volume of the music channel
+    ///
+    /// the range of values is `0.0` to `1.0`
+    pub fn get_volume(&self) -> f64 {
+        self.volume.get()
+    }
+
+    pub(super) fn set_bus_gain(&mut self, gain: f64) {
+        self.bus_gain = gain;
+        self.apply_volume();
+    }
+
+    fn apply_volume(&self) {
+        let volume = (self.volume.get() * self.bus_gain * 128.0) as i32;
+        mixer::Music::set_volume(volume);
+        MUSIC_LAYER.set_volume(volume);
+    }
+
+    /// Pauses the currently playing music
+    ///
+    /// does nothing if no music is playing
+    pub fn pause(&self) {
+        mixer::Music::pause();
+        MUSIC_LAYER.pause();
+        let mut clock = self.clock.get();
+        clock.pause();
+        self.clock.set(clock);
+    }
+
+    /// Resume paused music
+    ///
+    /// does nothing if no music has been paused
+    pub fn resume(&self) {
+        mixer::Music::resume();
+        MUSIC_LAYER.resume();
+        let mut clock = self.clock.get();
+        clock.resume();
+        self.clock.set(clock);
+    }
+
+    /// Stop the currently playing music and any playlist
+    pub fn stop(&mut self) {
+        self.playlist = None;
+        self.current = None;
+        mixer::Music::halt();
+        self.stop_layer();
+    }
+}
@@ -0,0 +1,338 @@
+//! Music decoded into memory and played on a sound effect channel,
+//! so that it can loop back to any point and be seeked exactly.
+//!
+//! The channel plays a silent chunk forever, and an sdl_mixer effect
+//! writes the music over it on the audio thread.
+
+use sdl2::mixer;
+use std::ffi::c_void;
+use std::os::raw::c_int;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+
+use crate::Error;
+use super::pcm::MixerSpec;
+
+/// The part of the player shared with the audio thread
+struct LoopState {
+    pcm: Arc<[u8]>,
+    /// the byte offset of the next audio to play
+    pos: AtomicUsize,
+    loop_start: usize,
+    loop_end: usize,
+    /// how many more times to go back to the loop start, or `-1` to loop forever
+    loops_left: AtomicI32,
+    ended: AtomicBool,
+}
+
+impl LoopState {
+    /// copy the next part of the music into the output,
+    /// leaving the silence in it once the music has ended
+    fn fill(&self, out: &mut [u8]) {
+        if self.ended.load(Ordering::Acquire) {
+            return;
+        }
+        let start = self.pos.load(Ordering::Acquire);
+        let mut pos = start;
+        let mut written = 0;
+        while written < out.len() {
+            if pos >= self.loop_end {
+                match self.loops_left.load(Ordering::Acquire) {
+                    0 => {
+                        self.ended.store(true, Ordering::Release);
+                        break;
+                    },
+                    n if n > 0 => self.loops_left.store(n - 1, Ordering::Release),
+                    _ => (),
+                }
+                pos = self.loop_start;
+            }
+            let n = (self.loop_end - pos).min(out.len() - written);
+            out[written..written + n].copy_from_slice(&self.pcm[pos..pos + n]);
+            pos += n;
+            written += n;
+        }
+        // keep the new position if the music was seeked while filling
+        let _ = self.pos.compare_exchange(start, pos, Ordering::AcqRel, Ordering::Acquire);
+    }
+}
+
+/// Plays decoded music on a channel, from the start through to `loop_end`,
+/// then back to `loop_start` for each loop
+pub(crate) struct LoopPlayer {
+    state: Arc<LoopState>,
+    spec: MixerSpec,
+}
+
+impl LoopPlayer {
+    /// Start playing the music on the channel, fading in over `fade_ms`
+    ///
+    /// - `pcm` is audio in the mixer's format
+    /// - the loop points are in seconds, with `None` being the end of the music
+    /// - `loops` is the number of times to go back to the loop start, `-1` loops forever
+    #[allow(clippy::too_many_arguments)]
+    pub fn play(channel: mixer::Channel,
+                silence: &mixer::Chunk,
+                spec: MixerSpec,
+                pcm: Arc<[u8]>,
+                loop_start: f64,
+                loop_end: Option<f64>,
+                loops: i32,
+                fade_ms: i32) -> Result<LoopPlayer, Error> {
+        channel.halt();
+        let end = loop_end.map_or(pcm.len(), |e| seconds_to_bytes(e, spec)).min(pcm.len());
+        let start = seconds_to_bytes(loop_start, spec);
+        let state = Arc::new(LoopState {
+            pos: AtomicUsize::new(0),
+            // a loop with no length would never finish filling the output
+            loop_start: if start < end { start } else { 0 },
+            loop_end: end,
+            loops_left: AtomicI32::new(loops),
+            ended: AtomicBool::new(end == 0),
+            pcm,
+        });
+        let udata = Arc::into_raw(state.clone()) as *mut c_void;
+        // SAFETY: the effect holds its own reference to the state,
+        // which `drop_state` releases when sdl_mixer removes the effect
+        if unsafe { sdl2::sys::mixer::Mix_RegisterEffect(channel.0, Some(write_music), Some(drop_state), udata) } == 0 {
+            unsafe { drop(Arc::from_raw(udata as *const LoopState)) };
+            return Err(Error::AudioPlay("failed to play music from memory, sdl_mixer error: ".to_string()
+                                        + &sdl2::get_error()));
+        }
+        if let Err(e) = channel.fade_in(silence, -1, fade_ms) {
+            unsafe { sdl2::sys::mixer::Mix_UnregisterEffect(channel.0, Some(write_music)) };
+            return Err(Error::AudioPlay("failed to play music from memory, sdl_mixer error: ".to_string() + &e));
+        }
+        Ok(LoopPlayer { state, spec })
+    }
+
+    /// true once the music has played through all of its loops
+    pub fn ended(&self) -> bool {
+        self.state.ended.load(Ordering::Acquire)
+    }
+
+    /// the time in seconds from the start of the music that is playing
+    pub fn position(&self) -> f64 {
+        self.state.pos.load(Ordering::Acquire) as f64 / self.spec.bytes_per_second()
+    }
+
+    /// jump to a time in seconds from the start of the music
+    pub fn set_position(&self, seconds: f64) {
+        let pos = seconds_to_bytes(seconds, self.spec).min(self.state.pcm.len());
+        self.state.pos.store(pos, Ordering::Release);
+    }
+}
+
+/// the byte offset of the start of the frame playing at that time
+fn seconds_to_bytes(seconds: f64, spec: MixerSpec) -> usize {
+    (seconds.max(0.0) * spec.rate as f64) as usize * spec.frame_bytes()
+}
+
+unsafe extern "C" fn write_music(_channel: c_int, stream: *mut c_void, len: c_int, udata: *mut c_void) {
+    let state = &*(udata as *const LoopState);
+    state.fill(std::slice::from_raw_parts_mut(stream as *mut u8, len as usize));
+}
+
+unsafe extern "C" fn drop_state(_channel: c_int, udata: *mut c_void) {
+    drop(Arc::from_raw(udata as *const LoopState));
+}
+
+/// Read the loop points of an ogg vorbis or opus file in seconds
+/// from its `LOOPSTART` and `LOOPLENGTH` or `LOOPEND` comments,
+/// which are given in samples
+pub(crate) fn ogg_loop_tags(data: &[u8]) -> Option<(f64, Option<f64>)> {
+    let packets = ogg_packets(data, 2);
+    let (rate, comments) = match packets.as_slice() {
+        [id, c] if id.len() >= 16 && id.starts_with(b"\x01vorbis") && c.starts_with(b"\x03vorbis") =>
+            (u32::from_le_bytes([id[12], id[13], id[14], id[15]]) as f64, &c[7..]),
+        // opus always counts samples at 48kHz
+        [id, c] if id.starts_with(b"OpusHead") && c.starts_with(b"OpusTags") => (48000.0, &c[8..]),
+        _ => return None,
+    };
+    if rate <= 0.0 {
+        return None;
+    }
+    let tags = vorbis_comments(comments);
+    let tag = |key: &str| tags.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .and_then(|(_, v)| v.trim().parse::<f64>().ok());
+    let start = tag("LOOPSTART")?;
+    let end = tag("LOOPLENGTH").map(|l| start + l).or_else(|| tag("LOOPEND"));
+    Some((start / rate, end.map(|e| e / rate)))
+}
+
+/// the first `count` packets of the ogg stream, joined from the segments of its pages
+fn ogg_packets(data: &[u8], count: usize) -> Vec<Vec<u8>> {
+    let mut packets = Vec::new();
+    let mut packet = Vec::new();
+    let mut i = 0;
+    while packets.len() < count && i + 27 <= data.len() && &data[i..i + 4] == b"OggS" {
+        let segments = data[i + 26] as usize;
+        let table = match data.get(i + 27..i + 27 + segments) {
+            Some(t) => t,
+            None => break,
+        };
+        let mut body = i + 27 + segments;
+        for &len in table {
+            let len = len as usize;
+            match data.get(body..body + len) {
+                Some(s) => packet.extend_from_slice(s),
+                None => return packets,
+            }
+            body += len;
+            // a segment shorter than the maximum ends the packet
+            if len < 255 {
+                packets.push(std::mem::take(&mut packet));
+                if packets.len() == count {
+                    break;
+                }
+            }
+        }
+        i = body;
+    }
+    packets
+}
+
+/// the `KEY=value` pairs of a vorbis comment block, after its magic bytes
+fn vorbis_comments(data: &[u8]) -> Vec<(String, String)> {
+    let mut comments = Vec::new();
+    let mut i = 0;
+    let read_u32 = |i: &mut usize| -> Option<usize> {
+        let bytes = data.get(*i..*i + 4)?;
+        *i += 4;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+    let vendor = match read_u32(&mut i) {
+        Some(v) => v,
+        None => return comments,
+    };
+    i += vendor;
+    let count = read_u32(&mut i).unwrap_or(0);
+    for _ in 0..count {
+        let len = match read_u32(&mut i) {
+            Some(l) => l,
+            None => break,
+        };
+        let comment = match data.get(i..i + len) {
+            Some(c) => String::from_utf8_lossy(c),
+            None => break,
+        };
+        i += len;
+        if let Some((k, v)) = comment.split_once('=') {
+            comments.push((k.to_string(), v.to_string()));
+        }
+    }
+    comments
+}
+
+#[cfg(test)]
+mod music_loop_tests {
+    use super::*;
+
+    fn state(pcm: Vec<u8>, pos: usize, loop_start: usize, loop_end: usize, loops: i32) -> LoopState {
+        LoopState {
+            pcm: pcm.into(),
+            pos: AtomicUsize::new(pos),
+            loop_start,
+            loop_end,
+            loops_left: AtomicI32::new(loops),
+            ended: AtomicBool::new(false),
+        }
+    }
+
+    /// an ogg page holding the whole packets, laced into segments
+    fn ogg_page(packets: &[Vec<u8>]) -> Vec<u8> {
+        let mut table = Vec::new();
+        for p in packets {
+            table.extend(std::iter::repeat(255).take(p.len() / 255));
+            table.push((p.len() % 255) as u8);
+        }
+        let mut page = b"OggS".to_vec();
+        page.extend([0; 22]);
+        page.push(table.len() as u8);
+        page.extend(table);
+        page.extend(packets.concat());
+        page
+    }
+
+    fn comment_packet(magic: &[u8], comments: &[&str]) -> Vec<u8> {
+        let mut packet = magic.to_vec();
+        packet.extend(6u32.to_le_bytes());
+        packet.extend(b"vendor");
+        packet.extend((comments.len() as u32).to_le_bytes());
+        for c in comments {
+            packet.extend((c.len() as u32).to_le_bytes());
+            packet.extend(c.as_bytes());
+        }
+        packet
+    }
+
+    fn vorbis_file(rate: u32, comments: &[&str]) -> Vec<u8> {
+        let mut id = b"\x01vorbis".to_vec();
+        id.extend(0u32.to_le_bytes());
+        id.push(2);
+        id.extend(rate.to_le_bytes());
+        id.extend([0; 14]);
+        // the comments go on a second page, as in real files
+        [ogg_page(&[id]), ogg_page(&[comment_packet(b"\x03vorbis", comments)])].concat()
+    }
+
+    #[test]
+    fn test_vorbis_loop_tags() {
+        // a long comment makes the packet span more than one segment
+        let padding = format!("TITLE={}", "a".repeat(600));
+        let data = vorbis_file(44100, &[&padding, "LOOPSTART=44100", "LOOPLENGTH=88200"]);
+        assert!(ogg_loop_tags(&data) == Some((1.0, Some(3.0))));
+        let data = vorbis_file(22050, &["loopstart=11025", "LoopEnd=44100"]);
+        assert!(ogg_loop_tags(&data) == Some((0.5, Some(2.0))));
+        let data = vorbis_file(22050, &["LOOPSTART=0"]);
+        assert!(ogg_loop_tags(&data) == Some((0.0, None)));
+        assert!(ogg_loop_tags(&vorbis_file(44100, &["LOOPLENGTH=100"])).is_none());
+        assert!(ogg_loop_tags(&vorbis_file(0, &["LOOPSTART=100"])).is_none());
+    }
+
+    #[test]
+    fn test_opus_loop_tags() {
+        let mut head = b"OpusHead".to_vec();
+        head.extend([1, 2, 0, 0, 0x80, 0xBB, 0, 0, 0, 0, 0]);
+        let tags = comment_packet(b"OpusTags", &["LOOPSTART=48000", "LOOPEND=96000"]);
+        assert!(ogg_loop_tags(&ogg_page(&[head, tags])) == Some((1.0, Some(2.0))));
+    }
+
+    #[test]
+    fn test_malformed_ogg() {
+        let data = vorbis_file(44100, &["LOOPSTART=44100", "LOOPLENGTH=88200"]);
+        for len in 0..data.len() {
+            assert!(ogg_loop_tags(&data[..len]).is_none());
+        }
+        let mut not_ogg = data.clone();
+        not_ogg[0] = b'X';
+        assert!(ogg_loop_tags(&not_ogg).is_none());
+        // a comment count or length past the end of the packet
+        let mut packet = comment_packet(b"\x03vorbis", &["LOOPSTART=1"]);
+        let count = packet.len() - 15;
+        packet[count..count + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(vorbis_comments(&packet[7..]).is_empty());
+        assert!(vorbis_comments(&u32::MAX.to_le_bytes()).is_empty());
+    }
+
+    #[test]
+    fn test_fill_loops() {
+        // plays 4, 5, loops back to 2 once, then ends and leaves the silence
+        let s = state((0..10).collect(), 4, 2, 6, 1);
+        let mut out = [0; 8];
+        s.fill(&mut out);
+        assert!(out == [4, 5, 2, 3, 4, 5, 0, 0]);
+        assert!(s.ended.load(Ordering::Acquire));
+        let mut out = [7; 4];
+        s.fill(&mut out);
+        assert!(out == [7; 4]);
+
+        let s = state((0..10).collect(), 0, 2, 6, -1);
+        let mut out = [0; 10];
+        s.fill(&mut out);
+        assert!(out == [0, 1, 2, 3, 4, 5, 2, 3, 4, 5]);
+        assert!(s.pos.load(Ordering::Acquire) == 6);
+        assert!(!s.ended.load(Ordering::Acquire));
+    }
+}
@@ -0,0 +1,155 @@
+//! Helpers for working with raw audio in the format sdl_mixer was opened with
+
+use sdl2::mixer::{self, LoaderRWops};
+use sdl2::rwops::RWops;
+
+use crate::Error;
+
+/// The sample rate and layout of the audio sdl_mixer is playing
+#[derive(Clone, Copy)]
+pub(crate) struct MixerSpec {
+    pub rate: u32,
+    pub channels: u16,
+    pub format: mixer::AudioFormat,
+}
+
+impl MixerSpec {
+    pub fn query() -> Result<MixerSpec, Error> {
+        let (rate, format, channels) = mixer::query_spec().map_err(
+            |e| Error::AudioPlay("failed to query audio format, sdl_mixer error: ".to_string() + &e))?;
+        Ok(MixerSpec { rate: rate as u32, channels: channels as u16, format })
+    }
+
+    /// the size in bytes of one sample for every channel
+    pub fn frame_bytes(&self) -> usize {
+        // the low byte of an sdl audio format is its bit size
+        (self.format & 0xFF) as usize / 8 * self.channels as usize
+    }
+
+    pub fn bytes_per_second(&self) -> f64 {
+        (self.frame_bytes() * self.rate as usize) as f64
+    }
+}
+
+/// The chunk's audio as signed 16 bit samples, converted from the mixer's format
+pub(crate) fn chunk_samples(chunk: &mixer::Chunk, spec: MixerSpec) -> Vec<i16> {
+    // SAFETY: the chunk's buffer is valid while the chunk is borrowed
+    let bytes = unsafe { std::slice::from_raw_parts((*chunk.raw).abuf, (*chunk.raw).alen as usize) };
+    // sdl audio formats hold the bit size in the low byte and flags in the high byte
+    let size = (spec.format & 0xFF) as usize / 8;
+    let float = spec.format & 0x100 != 0;
+    let big_endian = spec.format & 0x1000 != 0;
+    let signed = spec.format & 0x8000 != 0;
+    if size == 0 {
+        return Vec::new();
+    }
+    bytes.chunks_exact(size).map(|b| {
+        let v = if big_endian {
+            b.iter().fold(0u32, |v, x| v << 8 | *x as u32)
+        } else {
+            b.iter().rev().fold(0u32, |v, x| v << 8 | *x as u32)
+        };
+        match (size, float, signed) {
+            (1, _, true) => (v as u8 as i8 as i16) << 8,
+            (1, _, false) => (v as i16 - 128) << 8,
+            (2, _, true) => v as u16 as i16,
+            (2, _, false) => (v as i32 - 32768) as i16,
+            (4, true, _) => (f32::from_bits(v).clamp(-1.0, 1.0) * 32767.0) as i16,
+            (4, false, _) => (v as i32 >> 16) as i16,
+            _ => 0,
+        }
+    }).collect()
+}
+
+/// Change the speed and pitch of interleaved samples by `pitch` times,
+/// with linear interpolation between the original samples
+pub(crate) fn resample(samples: &[i16], channels: usize, pitch: f64) -> Vec<i16> {
+    let frames = samples.len() / channels.max(1);
+    if frames == 0 || pitch <= 0.0 {
+        return samples.to_vec();
+    }
+    let out_frames = ((frames as f64 / pitch).ceil() as usize).max(1);
+    let mut out = Vec::with_capacity(out_frames * channels);
+    for i in 0..out_frames {
+        let src = i as f64 * pitch;
+        let before = (src.floor() as usize).min(frames - 1);
+        let after = (before + 1).min(frames - 1);
+        let t = src - src.floor();
+        for c in 0..channels {
+            let a = samples[before * channels + c] as f64;
+            let b = samples[after * channels + c] as f64;
+            out.push((a + (b - a) * t).round() as i16);
+        }
+    }
+    out
+}
+
+/// Make a chunk from signed 16 bit samples, converted by sdl_mixer to the format it is playing.
+///
+/// The samples are loaded as a wav file in memory
+/// so that sdl_mixer owns and frees the converted audio.
+pub(crate) fn chunk_from_samples(samples: &[i16], rate: u32, channels: u16) -> Result<mixer::Chunk, Error> {
+    let wav = wav_bytes(samples, rate, channels);
+    let rw = RWops::from_bytes(&wav).map_err(Error::AudioPlay)?;
+    rw.load_wav().map_err(
+        |e| Error::AudioPlay("failed to create sound from samples, sdl_mixer error: ".to_string() + &e))
+}
+
+/// a 16 bit pcm wav file holding the samples
+fn wav_bytes(samples: &[i16], rate: u32, channels: u16) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // pcm
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&rate.to_le_bytes());
+    wav.extend_from_slice(&(rate * channels as u32 * 2).to_le_bytes());
+    wav.extend_from_slice(&(channels * 2).to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for s in samples {
+        wav.extend_from_slice(&s.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod pcm_tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_length() {
+        let samples: Vec<i16> = (0..100).collect();
+        assert!(resample(&samples, 1, 1.0).len() == 100);
+        assert!(resample(&samples, 1, 2.0).len() == 50);
+        assert!(resample(&samples, 1, 0.5).len() == 200);
+        assert!(resample(&samples, 1, 3.0).len() == 34);
+        assert!(resample(&samples, 2, 2.0).len() == 50);
+        assert!(resample(&[], 1, 2.0).is_empty());
+        assert!(resample(&samples, 1, 0.0) == samples);
+    }
+
+    #[test]
+    fn test_resample_ramp() {
+        let ramp = [0, 100, 200, 300];
+        assert!(resample(&ramp, 1, 1.0) == ramp);
+        assert!(resample(&ramp, 1, 0.5) == [0, 50, 100, 150, 200, 250, 300, 300]);
+        assert!(resample(&ramp, 1, 2.0) == [0, 200]);
+        assert!(resample(&ramp, 1, 0.75) == [0, 75, 150, 225, 300, 300]);
+    }
+
+    #[test]
+    fn test_resample_channels() {
+        let mono = [0, 100, 200];
+        assert!(resample(&mono, 1, 0.5) == [0, 50, 100, 150, 200, 200]);
+        // channels are interpolated separately
+        let stereo = [0, -1000, 100, -2000, 200, -3000];
+        assert!(resample(&stereo, 2, 0.5)
+                == [0, -1000, 50, -1500, 100, -2000, 150, -2500, 200, -3000, 200, -3000]);
+        assert!(resample(&stereo, 2, 2.0) == [0, -1000, 200, -3000]);
+    }
+}
@@ -0,0 +1,166 @@
+use std::time::Instant;
+use crate::{resource::Music, resources::random::Rng};
+
+
+/// A list of [Music] tracks to play one after another, see [MusicManager::play_playlist](super::MusicManager::play_playlist)
+#[derive(Clone)]
+pub struct Playlist {
+    pub tracks: Vec<Music>,
+    /// play the tracks in a random order, shuffled again each time the playlist repeats
+    pub shuffle: bool,
+    /// start from the first track again after the last one finishes
+    pub repeat: bool,
+    /// seconds of silence between tracks
+    pub gap: f64,
+    /// seconds each track fades in over
+    pub fade_in: f64,
+    /// seconds each track fades out over before it ends
+    ///
+    /// finding when to start fading decodes each track once to get its length,
+    /// which is kept until the music is unloaded
+    pub fade_out: f64,
+}
+
+impl Playlist {
+    /// a playlist that plays the tracks in order and repeats, with no gaps or fades
+    pub fn new(tracks: Vec<Music>) -> Playlist {
+        Playlist { tracks, shuffle: false, repeat: true, gap: 0.0, fade_in: 0.0, fade_out: 0.0 }
+    }
+
+    pub fn shuffled(self, shuffle: bool) -> Playlist {
+        Playlist { shuffle, ..self }
+    }
+
+    pub fn repeating(self, repeat: bool) -> Playlist {
+        Playlist { repeat, ..self }
+    }
+
+    pub fn with_gap(self, seconds: f64) -> Playlist {
+        Playlist { gap: seconds, ..self }
+    }
+
+    pub fn with_fade_in(self, seconds: f64) -> Playlist {
+        Playlist { fade_in: seconds, ..self }
+    }
+
+    pub fn with_fade_out(self, seconds: f64) -> Playlist {
+        Playlist { fade_out: seconds, ..self }
+    }
+}
+
+/// the progress through a playing [Playlist]
+pub(super) struct PlaylistState {
+    pub(super) playlist: Playlist,
+    /// the indices of the tracks in the order they are played
+    pub(super) order: Vec<usize>,
+    /// the index into `order` of the next track
+    pub(super) next: usize,
+    /// when to start the next track, if waiting in a gap
+    pub(super) next_at: Option<Instant>,
+    /// the position in the current track to start fading it out at
+    pub(super) fade_out_at: Option<f64>,
+    pub(super) rng: Rng,
+}
+
+impl PlaylistState {
+    pub(super) fn new(playlist: Playlist) -> PlaylistState {
+        let mut state = PlaylistState {
+            order: (0..playlist.tracks.len()).collect(),
+            playlist,
+            next: 0,
+            next_at: None,
+            fade_out_at: None,
+            rng: Rng::from_time(),
+        };
+        state.reorder();
+        state
+    }
+
+    fn reorder(&mut self) {
+        if self.playlist.shuffle {
+            let last = self.order.last().copied();
+            self.rng.shuffle(&mut self.order);
+            // don't play the same track twice in a row when shuffling again on repeat
+            if self.order.len() > 1 && self.next != 0 && self.order.first().copied() == last {
+                self.order.swap(0, 1);
+            }
+        }
+    }
+
+    /// the next track to play, or `None` if the playlist is over
+    pub(super) fn advance(&mut self) -> Option<Music> {
+        if self.next >= self.order.len() {
+            if !self.playlist.repeat || self.order.is_empty() {
+                return None;
+            }
+            self.reorder();
+            self.next = 0;
+        }
+        let track = self.playlist.tracks[self.order[self.next]];
+        self.next += 1;
+        Some(track)
+    }
+}
+
+#[cfg(test)]
+mod playlist_tests {
+    use super::*;
+
+    fn state(playlist: Playlist, seed: u64) -> PlaylistState {
+        let mut state = PlaylistState::new(playlist);
+        state.rng = Rng::new(seed);
+        state.reorder();
+        state
+    }
+
+    fn playlist(len: usize) -> Playlist {
+        Playlist::new((0..len).map(|id| Music { id }).collect())
+    }
+
+    fn next_ids(state: &mut PlaylistState, count: usize) -> Vec<Option<usize>> {
+        (0..count).map(|_| state.advance().map(|m| m.id)).collect()
+    }
+
+    #[test]
+    fn test_in_order() {
+        let mut s = state(playlist(3), 1);
+        assert!(next_ids(&mut s, 7) == [Some(0), Some(1), Some(2), Some(0), Some(1), Some(2), Some(0)]);
+    }
+
+    #[test]
+    fn test_stop_at_end() {
+        let mut s = state(playlist(3).repeating(false), 1);
+        assert!(next_ids(&mut s, 5) == [Some(0), Some(1), Some(2), None, None]);
+        let mut s = state(playlist(0), 1);
+        assert!(s.advance().is_none());
+    }
+
+    #[test]
+    fn test_shuffle() {
+        for seed in 0..50 {
+            let mut s = state(playlist(4).shuffled(true), seed);
+            let ids: Vec<usize> = next_ids(&mut s, 40).into_iter().map(|id| id.unwrap()).collect();
+            for round in ids.chunks(4) {
+                let mut sorted = round.to_vec();
+                sorted.sort();
+                assert!(sorted == [0, 1, 2, 3]);
+            }
+            // no track plays twice in a row, including across reshuffles
+            assert!(ids.windows(2).all(|w| w[0] != w[1]));
+        }
+    }
+
+    #[test]
+    fn test_shuffle_stop_at_end() {
+        let mut s = state(playlist(3).shuffled(true).repeating(false), 7);
+        let ids = next_ids(&mut s, 4);
+        assert!(ids[..3].iter().all(|id| id.is_some()));
+        assert!(ids[3].is_none());
+    }
+
+    #[test]
+    fn test_shuffle_single_track() {
+        let mut s = state(playlist(1).shuffled(true), 3);
+        assert!(next_ids(&mut s, 3) == [Some(0), Some(0), Some(0)]);
+    }
+}
@@ -0,0 +1,619 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+use sdl2::mixer;
+use crate::{
+    Error,
+    Camera,
+    geometry::Vec2,
+    resource::SoundEffect,
+    resource::SfxPlayback,
+    use_resource,
+    unload_resource,
+    load_resource_helper,
+    resources::stats::ResourceStats,
+    resources::random::Rng,
+};
+use super::{Bus, SynthSound, to_ms};
+use super::music::MUSIC_LAYER;
+use super::pcm::{MixerSpec, chunk_from_samples, chunk_samples, resample};
+
+/// Limits on how a [SoundEffect] is played,
+/// so that a sound played many times at once does not clip or take every channel
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct VoiceLimits {
+    /// the most playbacks of the sound at once,
+    /// playing it again stops the oldest playback. `None` for no limit
+    pub max_instances: Option<usize>,
+    /// seconds after the sound is played before it can be played again,
+    /// plays during this time are skipped
+    pub cooldown: f64,
+    /// when every channel is busy, the sound replaces the oldest playback
+    /// with the lowest priority that is not higher than its own
+    pub priority: i32,
+}
+
+impl Default for VoiceLimits {
+    fn default() -> Self {
+        VoiceLimits { max_instances: None, cooldown: 0.0, priority: 0 }
+    }
+}
+
+/// how a sound effect is played
+#[derive(Clone, Copy)]
+struct SoundSettings {
+    bus: Bus,
+    limits: VoiceLimits,
+    last_played: Option<Instant>,
+    /// how many semitones higher or lower each playback can randomly be
+    pitch_variation: f64,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        SoundSettings { bus: Bus::Sfx, limits: VoiceLimits::default(), last_played: None, pitch_variation: 0.0 }
+    }
+}
+
+/// the most pitched copies of a sound effect kept in memory
+const PITCH_VARIANTS: usize = 8;
+
+/// the range that pitches given to [SfxManager::play_pitched] are clamped to
+const MIN_PITCH: f64 = 0.1;
+const MAX_PITCH: f64 = 10.0;
+
+/// a copy of a sound effect resampled to play at a different pitch
+struct PitchVariant {
+    /// hundredths of a semitone higher than the sound
+    cents: i32,
+    chunk: mixer::Chunk,
+    /// when the variant was last played, from the manager's `play_count`
+    last_used: u64,
+}
+
+/// Load and play [SoundEffect], created and owned by [AudioManager](super::AudioManager)
+pub struct SfxManager {
+    sound_effects: Vec<Option<mixer::Chunk>>,
+    sound_effects_paths: HashMap<String, usize>,
+    sound_settings: Vec<SoundSettings>,
+    /// the pitched copies of each sound effect, made when they are first played
+    pitch_variants: Vec<Vec<PitchVariant>>,
+    channels: Vec<ChannelState>,
+    /// the number of playbacks started, for finding the oldest playback
+    play_count: u64,
+    /// the volume of each bus set by the [AudioManager](super::AudioManager), `0` if muted
+    bus_gains: HashMap<Bus, f64>,
+    /// the centre of the camera's view, positional sounds are heard relative to this
+    listener: Vec2,
+    /// how far from the listener positional sounds can be heard
+    hearing_range: f64,
+    /// for picking random pitches
+    rng: Rng,
+}
+
+/// the sound most recently played on a sound effect channel
+#[derive(Clone, Copy)]
+struct ChannelState {
+    /// the number of times the channel has been played on,
+    /// so that [SfxPlayback]s of earlier sounds on the channel can be ignored
+    generation: u64,
+    bus: Bus,
+    /// the volume of the playback, before the bus volume is applied
+    volume: f64,
+    /// where a positional sound is in the world
+    position: Option<Vec2>,
+    sound: usize,
+    /// the pitch of the sound's [PitchVariant] playing, `0` for the sound itself
+    cents: i32,
+    priority: i32,
+    /// when the playback was started, from the manager's `play_count`
+    started: u64,
+}
+
+impl SfxManager {
+    pub(super) fn new() -> SfxManager {
+        SfxManager {
+            sound_effects: Vec::new(),
+            sound_effects_paths: HashMap::new(),
+            sound_settings: Vec::new(),
+            pitch_variants: Vec::new(),
+            channels: Vec::new(),
+            play_count: 0,
+            bus_gains: HashMap::new(),
+            listener: Vec2::new(0.0, 0.0),
+            hearing_range: 0.0,
+            rng: Rng::from_time(),
+        }
+    }
+    
+    audio_load!(
+        /// load sound effect to memory
+        , load(self) -> SoundEffect,
+        self.sound_effects, self.sound_effects_paths, "Sound Effect",
+        mixer::Chunk::from_file
+    );
+
+    /// Render the synthesised sound and load it as a sound effect
+    ///
+    /// The sound is listed in [SfxManager::stats] as `<synth id>`
+    pub fn load_synth(&mut self, sound: &SynthSound) -> Result<SoundEffect, Error> {
+        let spec = MixerSpec::query()?;
+        let chunk = chunk_from_samples(&sound.render(spec.rate), spec.rate, 1)?;
+        let index = load_resource_helper!(check_and_push(self.sound_effects, Some(chunk)));
+        self.sound_effects_paths.insert(format!("<synth {}>", index), index);
+        log::info!("loaded synthesised Sound Effect - id: {}", index);
+        Ok(SoundEffect { id: index })
+    }
+
+    fn get_sfx(&mut self, sfx: SoundEffect) -> Result<&mut mixer::Chunk, Error> {
+        use_resource!(
+            self.sound_effects, sfx.id,
+            Some(s) => {
+                Ok(s)
+            }   
+        )
+    }
+
+    /// Plays the sound effect
+    ///
+    /// returns a handle for controlling this playback of the sound.
+    /// If the sound is skipped because of its [VoiceLimits],
+    /// the handle is of a playback that has already finished
+    pub fn play(&mut self, sfx: SoundEffect) -> Result<SfxPlayback, Error> {
+        self.play_looped(sfx, 0)
+    }
+
+    /// Plays the sound effect, then repeats it `loops` more times
+    ///
+    /// the sound will loop until stopped if you pass `-1`
+    pub fn play_looped(&mut self, sfx: SoundEffect, loops: i32) -> Result<SfxPlayback, Error> {
+        self.play_channel(sfx, loops, None, None)
+    }
+
+    /// Plays the sound effect faster or slower, which raises or lowers its pitch
+    ///
+    /// - a pitch of `2.0` is twice as fast and an octave higher, `0.5` is an octave lower
+    /// - the pitch is clamped between `0.1` and `10.0`
+    /// - the sound's pitch variation is not used
+    ///
+    /// The first time a sound is played at a pitch, a resampled copy of it is made.
+    /// A few copies are kept for each sound, and the least recently used are replaced,
+    /// stopping that copy if it is still playing when every copy is in use.
+    pub fn play_pitched(&mut self, sfx: SoundEffect, pitch: f64) -> Result<SfxPlayback, Error> {
+        self.play_channel(sfx, 0, None, Some(pitch))
+    }
+
+    /// Plays the sound effect at a position in the world,
+    /// panned and quietened by how far it is from the centre of the camera
+    ///
+    /// `set_listener` should be called each frame so the sound follows the camera,
+    /// and the position can be moved with `set_playback_position`
+    pub fn play_at(&mut self, sfx: SoundEffect, world_pos: Vec2) -> Result<SfxPlayback, Error> {
+        self.play_channel(sfx, 0, Some(world_pos), None)
+    }
+
+    /// Like `play_at`, but repeating the sound `loops` more times, or until stopped if you pass `-1`
+    pub fn play_looped_at(&mut self, sfx: SoundEffect, loops: i32, world_pos: Vec2) -> Result<SfxPlayback, Error> {
+        self.play_channel(sfx, loops, Some(world_pos), None)
+    }
+
+    /// Hear positional sounds from the centre of the camera's view
+    ///
+    /// Sounds are silent once they are a view's width away from the centre,
+    /// and are fully to one side at the edge of the view.
+    /// Call this each frame after moving the camera to update playing sounds.
+    pub fn set_listener(&mut self, cam: &Camera) -> Result<(), Error> {
+        let view = cam.get_view_size();
+        self.listener = cam.get_offset() + view * 0.5;
+        self.hearing_range = view.x;
+        for i in 0..self.channels.len() {
+            if self.channels[i].position.is_some() && mixer::Channel(i as i32).is_playing() {
+                self.apply_position(i)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move a playback started with `play_at`, ie to follow a moving object
+    ///
+    /// does nothing if the playback has finished or was not positional
+    pub fn set_playback_position(&mut self, playback: SfxPlayback, world_pos: Vec2) -> Result<(), Error> {
+        if let Some(c) = self.playback_channel(playback) {
+            let state = &mut self.channels[c.0 as usize];
+            if state.position.is_some() {
+                state.position = Some(world_pos);
+                return self.apply_position(c.0 as usize);
+            }
+        }
+        Ok(())
+    }
+
+    /// play the sound, at the pitch if there is one, otherwise at a random pitch in its variation
+    fn play_channel(&mut self, sfx: SoundEffect, loops: i32, position: Option<Vec2>, pitch: Option<f64>)
+                    -> Result<SfxPlayback, Error> {
+        self.get_sfx(sfx)?;
+        let settings = self.sound_settings(sfx);
+        let now = Instant::now();
+        if let Some(last) = settings.last_played {
+            if now.duration_since(last).as_secs_f64() < settings.limits.cooldown {
+                return Ok(SfxPlayback::skipped());
+            }
+        }
+        let channel = match self.choose_channel(sfx, &settings.limits) {
+            Some(c) => c,
+            None => {
+                log::debug!("skipped sound effect {}, every channel is playing a higher priority sound", sfx.id);
+                return Ok(SfxPlayback::skipped());
+            },
+        };
+        // a stolen channel is stopped so that it starts with the new sound's volume
+        channel.halt();
+        self.sound_settings[sfx.id].last_played = Some(now);
+        self.play_count += 1;
+        let index = channel.0 as usize;
+        if self.channels.len() <= index {
+            self.channels.resize(index + 1, ChannelState {
+                generation: 0, bus: Bus::Sfx, volume: 1.0, position: None, sound: 0, cents: 0, priority: 0, started: 0,
+            });
+        }
+        let cents = match pitch {
+            Some(p) => (1200.0 * p.clamp(MIN_PITCH, MAX_PITCH).log2()).round() as i32,
+            // random pitches are rounded to quarter semitones so fewer variants are made
+            None => (self.rng.range(-settings.pitch_variation, settings.pitch_variation) * 4.0).round() as i32 * 25,
+        };
+        let bus = settings.bus;
+        let state = &mut self.channels[index];
+        state.generation += 1;
+        state.bus = bus;
+        state.volume = 1.0;
+        state.sound = sfx.id;
+        state.cents = cents;
+        state.priority = settings.limits.priority;
+        state.started = self.play_count;
+        // panning stays on the channel after the sound has finished
+        if state.position.is_some() && position.is_none() {
+            audio_effect_err(channel.unregister_all_effects())?;
+        }
+        state.position = position;
+        let generation = state.generation;
+        bus.group().add_channel(channel);
+        self.apply_volume(index);
+        self.apply_position(index)?;
+        channel.play(self.pitched_chunk(sfx, cents)?, loops)
+            .map_err(
+                |e| Error::AudioPlay("failed to play sound effect, sdl_mixer error: ".to_string() + &e)
+            )?;
+        Ok(SfxPlayback { channel: channel.0, generation })
+    }
+
+    /// pick the channel to play the sound on, which may already be playing a sound to replace
+    ///
+    /// the oldest playback of the sound is replaced if it is at its limit,
+    /// otherwise a free channel is used, or the oldest playback of the lowest priority
+    /// that is not higher than the sound's priority
+    fn choose_channel(&self, sfx: SoundEffect, limits: &VoiceLimits) -> Option<mixer::Channel> {
+        let playing: Vec<usize> = (0..self.channels.len())
+            .filter(|i| self.channels[*i].generation > 0 && mixer::Channel(*i as i32).is_playing())
+            .collect();
+        if let Some(max) = limits.max_instances {
+            let instances: Vec<usize> = playing.iter().copied()
+                .filter(|i| self.channels[*i].sound == sfx.id)
+                .collect();
+            if instances.len() >= max.max(1) {
+                return self.oldest_lowest_priority(instances.into_iter());
+            }
+        }
+        // channels before the reserved music layer's are never used for sound effects
+        let free = (MUSIC_LAYER.0 + 1..mixer::allocate_channels(-1))
+            .map(mixer::Channel)
+            .find(|c| !c.is_playing());
+        free.or_else(|| self.oldest_lowest_priority(
+            playing.into_iter().filter(|i| self.channels[*i].priority <= limits.priority)))
+    }
+
+    /// the sound effect resampled to the pitch, reusing a variant made before if there is one
+    fn pitched_chunk(&mut self, sfx: SoundEffect, cents: i32) -> Result<&mixer::Chunk, Error> {
+        if cents == 0 {
+            return Ok(self.get_sfx(sfx)?);
+        }
+        let volume = self.get_sfx(sfx)?.get_volume();
+        if self.pitch_variants.len() <= sfx.id {
+            self.pitch_variants.resize_with(sfx.id + 1, Vec::new);
+        }
+        let index = match self.pitch_variants[sfx.id].iter().position(|v| v.cents == cents) {
+            Some(i) => i,
+            None => {
+                let spec = MixerSpec::query()?;
+                let samples = chunk_samples(self.get_sfx(sfx)?, spec);
+                let pitched = resample(&samples, spec.channels as usize, 2f64.powf(cents as f64 / 1200.0));
+                let chunk = chunk_from_samples(&pitched, spec.rate, spec.channels)?;
+                let variant = PitchVariant { cents, chunk, last_used: 0 };
+                let variants = &self.pitch_variants[sfx.id];
+                let in_use = |v: &PitchVariant| self.channels.iter().enumerate().any(|(i, c)| {
+                    c.sound == sfx.id && c.cents == v.cents && mixer::Channel(i as i32).is_playing()
+                });
+                if variants.len() < PITCH_VARIANTS {
+                    self.pitch_variants[sfx.id].push(variant);
+                    self.pitch_variants[sfx.id].len() - 1
+                } else {
+                    // replace the least recently used variant, preferring one that isn't playing
+                    let i = (0..variants.len())
+                        .min_by_key(|i| (in_use(&variants[*i]), variants[*i].last_used))
+                        .unwrap();
+                    self.pitch_variants[sfx.id][i] = variant;
+                    i
+                }
+            },
+        };
+        let variant = &mut self.pitch_variants[sfx.id][index];
+        variant.last_used = self.play_count;
+        variant.chunk.set_volume(volume);
+        Ok(&variant.chunk)
+    }
+
+    fn oldest_lowest_priority(&self, channels: impl Iterator<Item = usize>) -> Option<mixer::Channel> {
+        channels
+            .min_by_key(|i| (self.channels[*i].priority, self.channels[*i].started))
+            .map(|i| mixer::Channel(i as i32))
+    }
+
+    /// Set the [Bus] the sound effect plays on, sound effects start on `Bus::Sfx`
+    ///
+    /// playbacks that have already started stay on their bus
+    pub fn set_bus(&mut self, sfx: SoundEffect, bus: Bus) -> Result<(), Error> {
+        self.get_sfx(sfx)?;
+        self.sound_settings_mut(sfx).bus = bus;
+        Ok(())
+    }
+
+    /// Get the [Bus] the sound effect plays on
+    pub fn get_bus(&self, sfx: SoundEffect) -> Bus {
+        self.sound_settings(sfx).bus
+    }
+
+    /// Set how many playbacks of the sound effect there can be,
+    /// how often it can be played, and its priority for taking channels from other sounds
+    pub fn set_voice_limits(&mut self, sfx: SoundEffect, limits: VoiceLimits) -> Result<(), Error> {
+        self.get_sfx(sfx)?;
+        self.sound_settings_mut(sfx).limits = limits;
+        Ok(())
+    }
+
+    /// Get the [VoiceLimits] of the sound effect
+    pub fn get_voice_limits(&self, sfx: SoundEffect) -> VoiceLimits {
+        self.sound_settings(sfx).limits
+    }
+
+    /// Play the sound effect up to `semitones` higher or lower at random,
+    /// so that repeated sounds are less monotonous
+    ///
+    /// Sounds start with no variation. The random pitches are rounded to quarter semitones,
+    /// and each is resampled the first time it is played, see [SfxManager::play_pitched].
+    pub fn set_pitch_variation(&mut self, sfx: SoundEffect, semitones: f64) -> Result<(), Error> {
+        self.get_sfx(sfx)?;
+        self.sound_settings_mut(sfx).pitch_variation = semitones.abs();
+        Ok(())
+    }
+
+    /// Get how many semitones higher or lower the sound effect is randomly played
+    pub fn get_pitch_variation(&self, sfx: SoundEffect) -> f64 {
+        self.sound_settings(sfx).pitch_variation
+    }
+
+    fn sound_settings(&self, sfx: SoundEffect) -> SoundSettings {
+        self.sound_settings.get(sfx.id).copied().unwrap_or_default()
+    }
+
+    fn sound_settings_mut(&mut self, sfx: SoundEffect) -> &mut SoundSettings {
+        if self.sound_settings.len() <= sfx.id {
+            self.sound_settings.resize(sfx.id + 1, SoundSettings::default());
+        }
+        &mut self.sound_settings[sfx.id]
+    }
+
+    /// Returns true if the playback has not finished or been stopped
+    ///
+    /// paused playbacks are still playing
+    pub fn is_playing(&self, playback: SfxPlayback) -> bool {
+        self.playback_channel(playback).is_some_and(|c| c.is_playing())
+    }
+
+    /// Stop the playback, does nothing if it has already finished
+    pub fn stop(&mut self, playback: SfxPlayback) {
+        if let Some(c) = self.playback_channel(playback) {
+            c.halt();
+        }
+    }
+
+    /// Pause the playback, does nothing if it has already finished
+    pub fn pause(&mut self, playback: SfxPlayback) {
+        if let Some(c) = self.playback_channel(playback) {
+            c.pause();
+        }
+    }
+
+    /// Resume the paused playback, does nothing if it has already finished
+    pub fn resume(&mut self, playback: SfxPlayback) {
+        if let Some(c) = self.playback_channel(playback) {
+            c.resume();
+        }
+    }
+
+    /// Fade the playback out to silence over `seconds`, then stop it
+    pub fn fade_out(&mut self, playback: SfxPlayback, seconds: f64) {
+        if let Some(c) = self.playback_channel(playback) {
+            c.fade_out(to_ms(seconds));
+        }
+    }
+
+    /// Set the volume of just this playback, which is mixed with the sound effect's volume
+    ///
+    /// the range of volume values is `0.0` to `1.0`
+    pub fn set_playback_volume(&mut self, playback: SfxPlayback, volume: f64) {
+        if let Some(c) = self.playback_channel(playback) {
+            self.channels[c.0 as usize].volume = volume;
+            self.apply_volume(c.0 as usize);
+        }
+    }
+
+    /// the channel the playback is on, if the channel has not been played on since
+    fn playback_channel(&self, playback: SfxPlayback) -> Option<mixer::Channel> {
+        match self.channels.get(playback.channel as usize) {
+            Some(c) if c.generation == playback.generation => Some(mixer::Channel(playback.channel)),
+            _ => None,
+        }
+    }
+
+    /// the channels last played on with sounds on the bus
+    fn bus_channels(&self, bus: Bus) -> impl Iterator<Item = mixer::Channel> + '_ {
+        (0..self.channels.len())
+            .filter(move |i| self.is_sfx_channel(*i) && bus.contains(self.channels[*i].bus))
+            .map(|i| mixer::Channel(i as i32))
+    }
+
+    /// true if sound effects have been played on the channel,
+    /// which is never the case for the music layer's channel, as its volume is set by the [MusicManager](super::MusicManager)
+    fn is_sfx_channel(&self, channel: usize) -> bool {
+        channel as i32 != MUSIC_LAYER.0 && self.channels.get(channel).is_some_and(|c| c.generation > 0)
+    }
+
+    pub(super) fn bus_playing(&self, bus: Bus) -> bool {
+        self.bus_channels(bus).any(|c| c.is_playing() && !c.is_paused())
+    }
+
+    pub(super) fn pause_bus(&mut self, bus: Bus) {
+        self.bus_channels(bus).for_each(|c| c.pause());
+    }
+
+    pub(super) fn resume_bus(&mut self, bus: Bus) {
+        self.bus_channels(bus).for_each(|c| c.resume());
+    }
+
+    pub(super) fn stop_bus(&mut self, bus: Bus) {
+        if bus == Bus::Master {
+            self.bus_channels(bus).for_each(|c| c.halt());
+        } else {
+            bus.group().halt();
+        }
+    }
+
+    pub(super) fn set_bus_gain(&mut self, bus: Bus, gain: f64) {
+        self.bus_gains.insert(bus, gain);
+        let channels: Vec<mixer::Channel> = self.bus_channels(bus).collect();
+        for c in channels {
+            self.apply_volume(c.0 as usize);
+        }
+    }
+
+    /// pan and quieten the channel by how far its sound is from the listener
+    fn apply_position(&self, channel: usize) -> Result<(), Error> {
+        let pos = match self.channels[channel].position {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let (pan, distance) = if self.hearing_range > 0.0 {
+            let offset = pos - self.listener;
+            ((offset.x / (self.hearing_range / 2.0)).clamp(-1.0, 1.0),
+             ((offset.x * offset.x + offset.y * offset.y).sqrt() / self.hearing_range).min(1.0))
+        } else {
+            (0.0, 0.0)
+        };
+        let left = (255.0 * (1.0 - pan).min(1.0)) as u8;
+        let right = (255.0 * (1.0 + pan).min(1.0)) as u8;
+        let c = mixer::Channel(channel as i32);
+        audio_effect_err(c.set_panning(left, right))?;
+        audio_effect_err(c.set_distance((distance * 255.0) as u8))
+    }
+
+    /// set the channel's volume from the playback volume and the volumes of its bus
+    fn apply_volume(&self, channel: usize) {
+        if !self.is_sfx_channel(channel) {
+            return;
+        }
+        let state = self.channels[channel];
+        let gain = |bus| *self.bus_gains.get(&bus).unwrap_or(&1.0);
+        let mut volume = state.volume * gain(Bus::Master);
+        if state.bus != Bus::Master {
+            volume *= gain(state.bus);
+        }
+        mixer::Channel(channel as i32).set_volume((volume * 128.0) as i32);
+    }
+
+    /// unloades the internal `Sound Effect`
+    pub fn unload(&mut self, sfx: SoundEffect) {
+        if let Some(s) = self.sound_settings.get_mut(sfx.id) {
+            *s = SoundSettings::default();
+        }
+        if let Some(v) = self.pitch_variants.get_mut(sfx.id) {
+            v.clear();
+        }
+        self.unload_chunk(sfx);
+    }
+
+    unload_resource!(
+        , fn unload_chunk, self, self.sound_effects_paths, self.sound_effects, sfx, SoundEffect, "Sound Effect");
+
+    /// Get the sound effects currently loaded, with their memory use
+    ///
+    /// The size is of the decoded audio data held by sdl_mixer, including pitched copies
+    pub fn stats(&self) -> ResourceStats {
+        // SAFETY: the chunks are owned by this manager, so their pointers are valid
+        let size = |c: &mixer::Chunk| unsafe { (*c.raw).alen as usize };
+        ResourceStats::from_paths("Sound Effect", &self.sound_effects_paths, |_, id| {
+            match &self.sound_effects[id] {
+                Some(c) => size(c) + self.pitch_variants.get(id)
+                    .map_or(0, |v| v.iter().map(|v| size(&v.chunk)).sum()),
+                None => 0,
+            }
+        })
+    }
+
+    /// Set the volume of the sound effect
+    ///
+    /// returns error if the resource could not be found
+    ///
+    /// the range of volume values is `0.0` to `1.0`
+    pub fn set_volume(&mut self, sfx: SoundEffect, volume: f64) -> Result<(), Error> {
+        self.get_sfx(sfx)?.set_volume((volume * 128.0) as i32);
+        Ok(())
+    }
+
+    /// Get the volume of the sound effect
+    ///
+    /// returns error if the resource could not be found
+    ///
+    /// the range of volume values is `0.0` to `1.0`
+    pub fn get_volume(&mut self, sfx: SoundEffect) -> Result<f64, Error> {
+        Ok(self.get_sfx(sfx)?.get_volume() as f64 / 128.0)
+    }
+}
+
+fn audio_effect_err(result: Result<(), String>) -> Result<(), Error> {
+    result.map_err(|e| Error::AudioPlay("failed to set sound effect position, sdl_mixer error: ".to_string() + &e))
+}
+
+#[cfg(test)]
+mod sfx_tests {
+    use super::*;
+
+    fn state(bus: Bus, generation: u64) -> ChannelState {
+        ChannelState {
+            generation, bus, volume: 1.0, position: None, sound: 0, cents: 0, priority: 0, started: 0,
+        }
+    }
+
+    #[test]
+    fn test_bus_channels_skip_music_layer() {
+        let mut sfx = SfxManager::new();
+        // the music layer gets a default state when channels after it are played on
+        sfx.channels = vec![state(Bus::Sfx, 0), state(Bus::Sfx, 2), state(Bus::Ui, 1), state(Bus::Sfx, 0)];
+        let channels = |sfx: &SfxManager, bus| sfx.bus_channels(bus).map(|c| c.0).collect::<Vec<i32>>();
+        assert!(channels(&sfx, Bus::Sfx) == vec![1]);
+        assert!(channels(&sfx, Bus::Ui) == vec![2]);
+        assert!(channels(&sfx, Bus::Master) == vec![1, 2]);
+        assert!(!sfx.is_sfx_channel(MUSIC_LAYER.0 as usize));
+        sfx.channels[0].generation = 1;
+        assert!(!sfx.is_sfx_channel(MUSIC_LAYER.0 as usize));
+        assert!(channels(&sfx, Bus::Master) == vec![1, 2]);
+    }
+}
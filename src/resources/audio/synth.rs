@@ -0,0 +1,265 @@
+//! Retro sound effects made from simple waves, in the style of sfxr
+
+use std::f64::consts::TAU;
+
+use crate::resources::random::Rng;
+
+/// The shape of the wave a [SynthSound] is made from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Saw,
+    Sine,
+    Noise,
+}
+
+/// The settings of a synthesised sound effect, played by loading it with
+/// [crate::manager::SfxManager::load_synth]
+///
+/// Times are in seconds, and slides are in octaves per second.
+/// Start from [Default] or one of the presets, and use [SynthSound::mutate] for variations.
+/// Rendering the same settings always gives the same samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SynthSound {
+    pub waveform: Waveform,
+    /// the fraction of each cycle that a square wave is high, from `0.0` to `1.0`
+    pub duty: f64,
+    /// time to rise from silence to full volume
+    pub attack: f64,
+    /// time held at full volume
+    pub sustain: f64,
+    /// extra volume at the start of the sustain that falls away, from `0.0` to `1.0`
+    pub punch: f64,
+    /// time to fall from full volume to silence
+    pub decay: f64,
+    /// the starting frequency in hertz, for noise this is how often it changes
+    pub frequency: f64,
+    /// the sound stops early if its frequency slides below this
+    pub min_frequency: f64,
+    /// how fast the frequency rises, or falls if negative
+    pub slide: f64,
+    /// how fast the slide changes, in octaves per second per second
+    pub delta_slide: f64,
+    /// how far the frequency wobbles, in semitones
+    pub vibrato_depth: f64,
+    /// how many times a second the frequency wobbles
+    pub vibrato_speed: f64,
+    /// the frequency is multiplied by this once, after `arpeggio_delay`. `1.0` for no change
+    pub arpeggio: f64,
+    pub arpeggio_delay: f64,
+    /// from `0.0` to `1.0`
+    pub volume: f64,
+}
+
+impl Default for SynthSound {
+    /// a short square wave beep
+    fn default() -> Self {
+        SynthSound {
+            waveform: Waveform::Square,
+            duty: 0.5,
+            attack: 0.0,
+            sustain: 0.1,
+            punch: 0.0,
+            decay: 0.2,
+            frequency: 440.0,
+            min_frequency: 0.0,
+            slide: 0.0,
+            delta_slide: 0.0,
+            vibrato_depth: 0.0,
+            vibrato_speed: 0.0,
+            arpeggio: 1.0,
+            arpeggio_delay: 0.0,
+            volume: 0.5,
+        }
+    }
+}
+
+impl SynthSound {
+    /// a rising blip, different for each seed
+    pub fn jump(seed: u64) -> SynthSound {
+        let mut rng = Rng::new(seed);
+        SynthSound {
+            duty: rng.range(0.2, 0.6),
+            sustain: rng.range(0.05, 0.15),
+            decay: rng.range(0.1, 0.25),
+            frequency: rng.range(250.0, 500.0),
+            slide: rng.range(2.0, 4.0),
+            ..Default::default()
+        }
+    }
+
+    /// a bright chime that jumps up in pitch, different for each seed
+    pub fn coin(seed: u64) -> SynthSound {
+        let mut rng = Rng::new(seed);
+        SynthSound {
+            sustain: rng.range(0.02, 0.08),
+            punch: rng.range(0.3, 0.6),
+            decay: rng.range(0.1, 0.3),
+            frequency: rng.range(600.0, 1200.0),
+            arpeggio: rng.range(1.3, 1.6),
+            arpeggio_delay: rng.range(0.03, 0.08),
+            ..Default::default()
+        }
+    }
+
+    /// a short falling crunch, different for each seed
+    pub fn hit(seed: u64) -> SynthSound {
+        let mut rng = Rng::new(seed);
+        SynthSound {
+            waveform: if rng.next_f64() < 0.5 { Waveform::Saw } else { Waveform::Noise },
+            sustain: rng.range(0.01, 0.05),
+            punch: rng.range(0.0, 0.3),
+            decay: rng.range(0.05, 0.2),
+            frequency: rng.range(200.0, 700.0),
+            slide: rng.range(-8.0, -4.0),
+            ..Default::default()
+        }
+    }
+
+    /// a long rumble of noise, different for each seed
+    pub fn explosion(seed: u64) -> SynthSound {
+        let mut rng = Rng::new(seed);
+        SynthSound {
+            waveform: Waveform::Noise,
+            sustain: rng.range(0.1, 0.3),
+            punch: rng.range(0.3, 0.7),
+            decay: rng.range(0.3, 0.7),
+            frequency: rng.range(40.0, 120.0),
+            slide: rng.range(-0.5, 0.2),
+            vibrato_depth: rng.range(0.0, 0.5),
+            vibrato_speed: rng.range(5.0, 20.0),
+            ..Default::default()
+        }
+    }
+
+    /// A random variation of the sound, the same for the same seed
+    ///
+    /// `amount` is how different it can be, from `0.0` for no change to `1.0`
+    pub fn mutate(&self, seed: u64, amount: f64) -> SynthSound {
+        let mut rng = Rng::new(seed);
+        let mut r = || rng.range(-amount, amount);
+        let mut s = *self;
+        s.duty = (s.duty + r() * 0.5).clamp(0.05, 0.95);
+        s.attack = (s.attack * (1.0 + r())).max(0.0);
+        s.sustain = (s.sustain * (1.0 + r())).max(0.0);
+        s.punch = (s.punch + r()).clamp(0.0, 1.0);
+        s.decay = (s.decay * (1.0 + r())).max(0.0);
+        s.frequency *= 2f64.powf(r());
+        s.slide += r() * 2.0;
+        s.delta_slide += r();
+        s.vibrato_depth = (s.vibrato_depth + r() * 0.5).max(0.0);
+        s.vibrato_speed = (s.vibrato_speed * (1.0 + r())).max(0.0);
+        if s.arpeggio != 1.0 {
+            s.arpeggio *= 2f64.powf(r() * 0.5);
+        }
+        s
+    }
+
+    /// the length of the sound in seconds, if it doesn't stop early
+    pub fn duration(&self) -> f64 {
+        self.attack.max(0.0) + self.sustain.max(0.0) + self.decay.max(0.0)
+    }
+
+    /// Render the sound as mono signed 16 bit samples at the sample rate
+    pub fn render(&self, rate: u32) -> Vec<i16> {
+        let rate = rate.max(1) as f64;
+        let length = (self.duration() * rate) as usize;
+        let mut samples = Vec::with_capacity(length);
+        // noise is seeded the same every time so that rendering is repeatable
+        let mut rng = Rng::new(0);
+        let mut noise = [0.0; 32];
+        noise.iter_mut().for_each(|n| *n = rng.range(-1.0, 1.0));
+        let mut frequency = self.frequency.max(0.0);
+        let mut arpeggio_done = self.arpeggio == 1.0;
+        let mut phase = 0.0;
+        for i in 0..length {
+            let t = i as f64 / rate;
+            frequency *= 2f64.powf((self.slide + self.delta_slide * t) / rate);
+            if !arpeggio_done && t >= self.arpeggio_delay {
+                frequency *= self.arpeggio;
+                arpeggio_done = true;
+            }
+            if frequency < self.min_frequency {
+                break;
+            }
+            let vibrato = self.vibrato_depth / 12.0 * (TAU * self.vibrato_speed * t).sin();
+            // above half the sample rate the wave can't be represented
+            let f = (frequency * 2f64.powf(vibrato)).min(rate / 2.0);
+            phase += f / rate;
+            if phase >= 1.0 {
+                phase %= 1.0;
+                if self.waveform == Waveform::Noise {
+                    noise.iter_mut().for_each(|n| *n = rng.range(-1.0, 1.0));
+                }
+            }
+            let wave = match self.waveform {
+                Waveform::Square => if phase < self.duty { 1.0 } else { -1.0 },
+                Waveform::Saw => 1.0 - 2.0 * phase,
+                Waveform::Sine => (TAU * phase).sin(),
+                Waveform::Noise => noise[(phase * 32.0) as usize % 32],
+            };
+            let sample = wave * self.envelope(t) * self.volume.clamp(0.0, 1.0);
+            samples.push((sample.clamp(-1.0, 1.0) * i16::MAX as f64) as i16);
+        }
+        samples
+    }
+
+    /// the volume at the time through the sound
+    fn envelope(&self, t: f64) -> f64 {
+        let (attack, sustain, decay) = (self.attack.max(0.0), self.sustain.max(0.0), self.decay.max(0.0));
+        if t < attack {
+            t / attack
+        } else if t < attack + sustain {
+            1.0 + self.punch * (1.0 - (t - attack) / sustain)
+        } else if decay > 0.0 {
+            (1.0 - (t - attack - sustain) / decay).max(0.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod synth_tests {
+    use super::*;
+
+    #[test]
+    fn test_render_repeatable() {
+        for sound in [SynthSound::jump(3), SynthSound::coin(3), SynthSound::hit(3), SynthSound::explosion(3)] {
+            let samples = sound.render(22050);
+            assert!(!samples.is_empty());
+            assert!(samples == sound.render(22050));
+        }
+    }
+
+    #[test]
+    fn test_render_length() {
+        let sound = SynthSound { attack: 0.1, sustain: 0.2, decay: 0.2, ..Default::default() };
+        assert!(sound.render(1000).len() == 500);
+        let cut_off = SynthSound { slide: -10.0, min_frequency: 220.0, ..sound };
+        assert!(cut_off.render(1000).len() < 150);
+    }
+
+    #[test]
+    fn test_square_wave() {
+        let sound = SynthSound {
+            frequency: 100.0, attack: 0.0, sustain: 0.1, decay: 0.0, volume: 1.0, ..Default::default()
+        };
+        let samples = sound.render(1000);
+        // ten samples per cycle, high for the first half
+        assert!(samples[0..4].iter().all(|s| *s == i16::MAX));
+        assert!(samples[5..9].iter().all(|s| *s == -i16::MAX));
+        assert!(samples[10..14].iter().all(|s| *s == i16::MAX));
+    }
+
+    #[test]
+    fn test_presets_and_mutation() {
+        assert!(SynthSound::coin(1) == SynthSound::coin(1));
+        assert!(SynthSound::coin(1) != SynthSound::coin(2));
+        let jump = SynthSound::jump(1);
+        assert!(jump.mutate(5, 0.2) == jump.mutate(5, 0.2));
+        assert!(jump.mutate(5, 0.2) != jump.mutate(6, 0.2));
+        assert!(jump.mutate(5, 0.0) == jump);
+        assert!(jump.mutate(5, 0.2).render(22050) != jump.render(22050));
+    }
+}
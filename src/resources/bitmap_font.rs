@@ -0,0 +1,295 @@
+use sdl2::render::{TextureCreator, Texture, Canvas};
+use sdl2::video::Window;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{Colour, Error, Pixels, draw_err};
+use crate::resources::texture_manager::streaming_texture;
+use crate::geometry::*;
+
+/// A glyph in one of the font's page textures, measured in the font's pixels
+#[derive(Clone, Copy)]
+struct BitmapGlyph {
+    page: usize,
+    rect: Rect,
+    offset: Vec2,
+    advance: f64,
+}
+
+/// A font made of hand drawn glyphs in page images,
+/// loaded from an [AngelCode BMFont](https://www.angelcode.com/products/bmfont/) `.fnt` file
+/// in either the text or xml format.
+///
+/// The page images are kept in memory as well as in textures,
+/// so that [crate::TextObject]s can be rendered from them.
+pub(crate) struct BitmapFont<'a> {
+    pub line_height: f64,
+    pages: Vec<(Texture<'a>, Pixels)>,
+    glyphs: HashMap<char, BitmapGlyph>,
+    kerning: HashMap<(char, char), f64>,
+}
+
+impl<'a> BitmapFont<'a> {
+    /// load the .fnt file, and the page images it uses from the same folder
+    pub fn load<T>(path: &Path, texture_creator: &'a TextureCreator<T>) -> Result<BitmapFont<'a>, Error> {
+        let data = std::fs::read_to_string(path).map_err(|e| Error::LoadFile(
+            format!("failed to read bitmap font {}: {}", path.to_string_lossy(), e)))?;
+        let mut font = BitmapFont {
+            line_height: 0.0,
+            pages: Vec::new(),
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+        };
+        let mut page_files: Vec<(usize, String)> = Vec::new();
+        for (tag, attribs) in parse_records(&data) {
+            let num = |key: &str| attribs.get(key).and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+            let chr = |key: &str| char::from_u32(num(key) as u32).unwrap_or('\0');
+            match tag.as_str() {
+                "common" => font.line_height = num("lineHeight"),
+                "page" => page_files.push((
+                    num("id") as usize,
+                    attribs.get("file").cloned().unwrap_or_default())),
+                "char" => {
+                    font.glyphs.insert(chr("id"), BitmapGlyph {
+                        page: num("page") as usize,
+                        rect: Rect::new(num("x"), num("y"), num("width"), num("height")),
+                        offset: Vec2::new(num("xoffset"), num("yoffset")),
+                        advance: num("xadvance"),
+                    });
+                },
+                "kerning" => {
+                    font.kerning.insert((chr("first"), chr("second")), num("amount"));
+                },
+                _ => (),
+            }
+        }
+        if font.line_height <= 0.0 || page_files.is_empty() {
+            return Err(Error::LoadFile(format!(
+                "bitmap font {} is missing its common or page information", path.to_string_lossy())));
+        }
+        page_files.sort_by_key(|(id, _)| *id);
+        let folder = path.parent().unwrap_or(Path::new(""));
+        for (_, file) in page_files {
+            let pixels = Pixels::load(&folder.join(file))?;
+            let tex = streaming_texture(texture_creator, pixels.width, pixels.height, &pixels.rgba)?;
+            font.pages.push((tex, pixels));
+        }
+        Ok(font)
+    }
+
+    /// draw the text with its top left at `pos`,
+    /// with `scale` converting from the font's pixels to the canvas' pixels
+    pub fn draw(&mut self,
+                canvas: &mut Canvas<Window>,
+                text: &str,
+                pos: Vec2,
+                scale: Vec2,
+                colour: Colour) -> Result<(), Error> {
+        for (page, src, dst) in self.quads(text) {
+            let page = &mut self.pages[page].0;
+            page.set_color_mod(colour.r, colour.g, colour.b);
+            page.set_alpha_mod(colour.a);
+            draw_err!(canvas.copy(
+                page,
+                sdl2::rect::Rect::new(src.x as i32, src.y as i32, src.w as u32, src.h as u32),
+                sdl2::rect::Rect::new(
+                    (pos.x + dst.x * scale.x) as i32,
+                    (pos.y + dst.y * scale.y) as i32,
+                    (dst.w * scale.x).ceil() as u32,
+                    (dst.h * scale.y).ceil() as u32)))?;
+        }
+        Ok(())
+    }
+
+    /// the page, the source rect in the page and the destination rect
+    /// relative to the start of the text, in the font's pixels, of each glyph in the text
+    fn quads(&self, text: &str) -> Vec<(usize, Rect, Rect)> {
+        let mut quads = Vec::new();
+        self.walk(text, |g, pen| {
+            if g.rect.w > 0.0 && g.rect.h > 0.0 && g.page < self.pages.len() {
+                quads.push((g.page, g.rect,
+                            Rect::new(pen + g.offset.x, g.offset.y, g.rect.w, g.rect.h)));
+            }
+        });
+        quads
+    }
+
+    /// true if the font has a glyph for the character
+    pub fn has_glyph(&self, ch: char) -> bool {
+        self.glyphs.contains_key(&ch)
+    }
+
+    /// the position of the caret before the first and after each character of the text,
+    /// in the font's pixels
+    pub fn carets(&self, text: &str) -> Vec<f64> {
+        self.walk(text, |_, _| ())
+    }
+
+    pub fn width(&self, text: &str) -> f64 {
+        *self.carets(text).last().unwrap_or(&0.0)
+    }
+
+    /// estimated gpu memory used by the page textures
+    pub fn bytes(&self) -> usize {
+        self.pages.iter().map(|(_, p)| p.rgba.len()).sum()
+    }
+
+    /// draw the text into an rgba buffer, tinted by the colour,
+    /// returning the width, height and pixels
+    pub fn render(&self, text: &str, colour: Colour) -> (u32, u32, Vec<u8>) {
+        let w = (self.width(text).ceil() as u32).max(1);
+        let h = (self.line_height.ceil() as u32).max(1);
+        let mut out = vec![0; (w * h * 4) as usize];
+        for (page, src, dst) in self.quads(text) {
+            let pixels = &self.pages[page].1;
+            for y in 0..src.h as i64 {
+                for x in 0..src.w as i64 {
+                    let (tx, ty) = (dst.x as i64 + x, dst.y as i64 + y);
+                    if tx < 0 || ty < 0 || tx >= w as i64 || ty >= h as i64 {
+                        continue;
+                    }
+                    let p = match pixels.get((src.x as i64 + x) as u32, (src.y as i64 + y) as u32) {
+                        Some(p) => p,
+                        None => continue,
+                    };
+                    let i = ((ty as u32 * w + tx as u32) * 4) as usize;
+                    let a = p.a as u32 * colour.a as u32 / 255;
+                    if a >= out[i + 3] as u32 {
+                        out[i] = (p.r as u32 * colour.r as u32 / 255) as u8;
+                        out[i + 1] = (p.g as u32 * colour.g as u32 / 255) as u8;
+                        out[i + 2] = (p.b as u32 * colour.b as u32 / 255) as u8;
+                        out[i + 3] = a as u8;
+                    }
+                }
+            }
+        }
+        (w, h, out)
+    }
+
+    /// call `f` with each glyph and the pen position it is drawn at,
+    /// and return the caret positions before the first and after each character
+    fn walk(&self, text: &str, mut f: impl FnMut(&BitmapGlyph, f64)) -> Vec<f64> {
+        let mut pen = 0.0;
+        let mut carets = vec![0.0];
+        let mut prev: Option<char> = None;
+        for ch in text.chars() {
+            // characters missing from the font are drawn as ?
+            if let Some(g) = self.glyphs.get(&ch).or_else(|| self.glyphs.get(&'?')) {
+                if let Some(p) = prev {
+                    pen += self.kerning.get(&(p, ch)).unwrap_or(&0.0);
+                }
+                f(g, pen);
+                pen += g.advance;
+            }
+            carets.push(pen);
+            prev = Some(ch);
+        }
+        carets
+    }
+}
+
+/// Read the tags and attributes of a .fnt file,
+/// the text format has a tag and `key=value` pairs on each line,
+/// and the xml format has them as elements.
+fn parse_records(data: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut records = Vec::new();
+    let is_xml = data.trim_start().starts_with('<');
+    let entries: Vec<&str> = if is_xml {
+        data.split('<').filter_map(|e| e.split('>').next()).collect()
+    } else {
+        data.lines().collect()
+    };
+    for entry in entries {
+        let entry = entry.trim().trim_end_matches('/');
+        if entry.starts_with('?') || entry.starts_with('/') || entry.starts_with('!') {
+            continue;
+        }
+        let (tag, rest) = match entry.find(char::is_whitespace) {
+            Some(i) => (&entry[..i], &entry[i..]),
+            None => (entry, ""),
+        };
+        if tag.is_empty() {
+            continue;
+        }
+        records.push((tag.to_string(), parse_attributes(rest)));
+    }
+    records
+}
+
+fn parse_attributes(s: &str) -> HashMap<String, String> {
+    let mut attribs = HashMap::new();
+    let mut chars = s.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if key.is_empty() {
+            break;
+        }
+        let value: String = if chars.peek() == Some(&'"') {
+            chars.next();
+            chars.by_ref().take_while(|c| *c != '"').collect()
+        } else {
+            chars.by_ref().take_while(|c| !c.is_whitespace()).collect()
+        };
+        attribs.insert(key.trim().to_string(), value);
+    }
+    attribs
+}
+
+#[cfg(test)]
+mod bitmap_font_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_attributes() {
+        let a = parse_attributes(r#" id=65   x=3 y=-2 file="page 0.png" face="" "#);
+        assert!(a.len() == 5);
+        assert!(a["id"] == "65");
+        assert!(a["y"] == "-2");
+        assert!(a["file"] == "page 0.png");
+        assert!(a["face"].is_empty());
+        assert!(parse_attributes("").is_empty());
+        assert!(parse_attributes("   ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_text_records() {
+        let data = "info face=\"Pixel Font\" size=16\n\
+                    common lineHeight=18 base=14 pages=1\n\
+                    page id=0 file=\"pixel font_0.png\"\n\
+                    char id=65 x=0 y=0 width=8 height=10 xadvance=9 page=0\n\
+                    \n\
+                    kerning first=65 second=86 amount=-1\n";
+        let records = parse_records(data);
+        let tags: Vec<&str> = records.iter().map(|(t, _)| t.as_str()).collect();
+        assert!(tags == vec!["info", "common", "page", "char", "kerning"]);
+        assert!(records[0].1["face"] == "Pixel Font");
+        assert!(records[1].1["lineHeight"] == "18");
+        assert!(records[2].1["file"] == "pixel font_0.png");
+        assert!(records[4].1["amount"] == "-1");
+    }
+
+    #[test]
+    fn test_parse_xml_records() {
+        let data = r#"<?xml version="1.0"?>
+<font>
+  <!-- generated -->
+  <common lineHeight="18" base="14" pages="1"/>
+  <pages>
+    <page id="0" file="pixel font_0.png" />
+  </pages>
+  <chars count="1">
+    <char id="65" x="0" y="0" width="8" height="10" xadvance="9" page="0"/>
+  </chars>
+</font>"#;
+        let records = parse_records(data);
+        let tags: Vec<&str> = records.iter().map(|(t, _)| t.as_str()).collect();
+        assert!(tags == vec!["font", "common", "pages", "page", "chars", "char"]);
+        assert!(records[1].1["lineHeight"] == "18");
+        assert!(records[3].1["file"] == "pixel font_0.png");
+        assert!(records[5].1["xadvance"] == "9");
+    }
+}
@@ -1,15 +1,26 @@
-use sdl2::render::{TextureCreator, Canvas};
-use sdl2::{video::Window, pixels::Color, ttf};
+use sdl2::render::{TextureCreator, Canvas, BlendMode};
+use sdl2::{video::Window, pixels::{Color, PixelFormatEnum}, surface::Surface, ttf};
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::Path;
 
 use crate::{
-    resource::{Font, Text},
+    resource::{Font, FontKind, Text},
     Colour, Error,
     rect_conversion::{RectConversion, Vec2Conversion},
-    file_err, font_err, draw_err, unload_resource, load, load_resource_helper, draw,
+    file_err, font_err, draw_err, unload_resource, load_resource_helper, draw,
     TextObject,
+    resources::stats::{ResourceStats, file_size},
+    resources::glyph_atlas::GlyphAtlas,
+    resources::texture_manager::streaming_texture,
+    resources::bitmap_font::BitmapFont,
+    resources::text_layout::TextLayout,
+    resources::text_metrics::TextMetrics,
+    resources::font_settings::FontSettings,
+    resources::rich_text::parse_markup,
+    resources::text_effects::TextEffects,
+    Pixels,
 };
 
 use crate::geometry::*;
@@ -17,47 +28,164 @@ use crate::geometry::*;
 
 pub(crate) type TextDraw = crate::resources::types::TextureDraw;
 
-struct ResourceTextDraw<'a> {
-    tex  : sdl2::render::Texture<'a>,
-    rect : sdl2::rect::Rect,
-}
-
 pub(crate) struct DisposableTextDraw {
     pub font : Font,
     pub text: String,
-    pub height : u32,
     pub pos : Vec2,
     pub colour : Colour,
     pub rect: Rect,
+    pub effects: TextEffects,
+}
+
+/// what a text draw was rendered from, so that it can be rendered again
+#[derive(Clone)]
+struct TextSource {
+    font: Font,
+    text: String,
+    colour: Colour,
+    effects: TextEffects,
+    /// the space in pixels added to the left, top, right and bottom of the text for the effects
+    margin: [u32; 4],
 }
 
-const FONT_LOAD_SIZE : u16 = 128;
+pub(crate) const FONT_LOAD_SIZE : u16 = 128;
 
-/// Stores [sdl2::ttf::Font]s and creates [Font]s or [TextObject]s.
+/// Stores [sdl2::ttf::Font]s and bitmap fonts and creates [Font]s or [TextObject]s.
 /// Created and owned by [crate::Render]
 pub struct FontManager<'a, T> {
     texture_creator : &'a TextureCreator<T>,
     ttf_context: &'a ttf::Sdl2TtfContext,
     loaded_font_paths : HashMap<String, usize>,
     pub fonts : Vec<Option<ttf::Font<'a, 'static>>>,
+    /// whether ttf fonts have glyphs for characters above `U+FFFF`, found by rendering them
+    rendered_glyphs: RefCell<HashMap<(usize, char), bool>>,
+    /// the file and settings each font was loaded with
+    font_sources: Vec<(String, FontSettings)>,
     text_draws: Vec<Option<sdl2::render::Texture<'a>>>,
+    text_sources: Vec<Option<TextSource>>,
+    glyph_atlases: Vec<Option<GlyphAtlas<'a>>>,
+    loaded_bitmap_font_paths : HashMap<String, usize>,
+    bitmap_fonts: Vec<Option<BitmapFont<'a>>>,
+    /// the fonts of each family, in the order they are checked for glyphs
+    font_families: Vec<Option<Vec<Font>>>,
+    /// the styles of each ttf font loaded by `load_rich_text`, unloaded with the font
+    style_fonts: HashMap<usize, Vec<Font>>,
 }
 
 impl<'a, T: 'a> FontManager<'a, T> {
     
     //load a ttf font face to memory and get a [Font] object that references it
     pub fn load_font(&mut self, path : &Path) -> Result<Font, Error>{
-        let font_index =
-            load!(path, self.fonts, self.loaded_font_paths, self.ttf_context, "Font", FONT_LOAD_SIZE);
+        self.load_font_with(path, &FontSettings::default())
+    }
+
+    /// load a ttf font face with a style, outline, hinting or pixel size
+    /// and get a [Font] object that references it
+    pub fn load_font_with(&mut self, path : &Path, settings: &FontSettings) -> Result<Font, Error> {
+        let path_string = path.to_string_lossy().to_string();
+        let key = settings.path_key(&path_string);
+        if let Some(id) = self.loaded_font_paths.get(&key) {
+            return Ok(Font { id: *id, kind: FontKind::Ttf });
+        }
+        let mut font = file_err!(self.ttf_context.load_font(path, settings.size))?;
+        settings.apply(&mut font);
+        let font_index = load_resource_helper!(check_and_push(self.fonts, Some(font)));
+        self.loaded_font_paths.insert(key.clone(), font_index);
+        if self.font_sources.len() <= font_index {
+            self.font_sources.resize(font_index + 1, (String::new(), FontSettings::default()));
+        }
+        self.font_sources[font_index] = (path_string, *settings);
+        // a new font may reuse the slot of an unloaded one, so clear any old glyphs
+        if font_index < self.glyph_atlases.len() {
+            self.glyph_atlases[font_index] = None;
+        }
+        log::info!("loaded Font - id: {} - path: {}", font_index, key);
         Ok(
             Font {
             id: font_index,
+            kind: FontKind::Ttf,
         })
     }
-    
+
+    /// load an [AngelCode BMFont](https://www.angelcode.com/products/bmfont/) `.fnt` file,
+    /// in the text or xml format, and get a [Font] object that references it
+    ///
+    /// The font's page images are loaded from the same folder as the .fnt file.
+    /// They are kept in textures owned by the font rather than the [crate::manager::TextureManager],
+    /// so they are freed by unloading the font and counted in `stats`.
+    /// Bitmap fonts can be used anywhere a ttf [Font] can,
+    /// but ignore any bold, italic, underline or strikethrough styles.
+    pub fn load_bitmap_font(&mut self, path : &Path) -> Result<Font, Error> {
+        let path_string = path.to_string_lossy().to_string();
+        if let Some(id) = self.loaded_bitmap_font_paths.get(&path_string) {
+            return Ok(Font { id: *id, kind: FontKind::Bitmap });
+        }
+        let font = BitmapFont::load(path, self.texture_creator)?;
+        let font_index = load_resource_helper!(check_and_push(self.bitmap_fonts, Some(font)));
+        self.loaded_bitmap_font_paths.insert(path_string, font_index);
+        log::info!("loaded bitmap Font - id: {} - path: {}", font_index, path.to_string_lossy());
+        Ok(Font { id: font_index, kind: FontKind::Bitmap })
+    }
+
+    /// get a [Font] that draws each character with the first font in the list that has a glyph for it,
+    /// so that text can mix scripts or emoji that are not in a single font file
+    ///
+    /// Characters that none of the fonts have are drawn with the first font.
+    /// Each font is scaled so that its line fills the height the text is drawn at.
+    /// Unloading the family does not unload the fonts in it,
+    /// and the fonts must stay loaded while the family is used.
+    pub fn load_font_family(&mut self, fonts: &[Font]) -> Result<Font, Error> {
+        let mut family = Vec::new();
+        for font in fonts {
+            match font.kind {
+                FontKind::Family => family.extend_from_slice(self.get_font_family(font)?),
+                _ => {
+                    self.text_width(font, "", 1.0)?;
+                    family.push(*font);
+                },
+            }
+        }
+        if family.is_empty() {
+            return Err(Error::MissingResource(String::from("Font family has no fonts")));
+        }
+        let font_index = load_resource_helper!(check_and_push(self.font_families, Some(family)));
+        log::info!("loaded Font family - id: {} - fonts: {}", font_index, fonts.len());
+        Ok(Font { id: font_index, kind: FontKind::Family })
+    }
+
+    ///unloades the [Font] stored by the sdl2 context, it can no longer be used
+    pub fn unload(&mut self, font: Font) {
+        match font.kind {
+            FontKind::Ttf => {
+                self.unload_ttf(font);
+                self.rendered_glyphs.get_mut().retain(|(id, _), _| *id != font.id);
+                // free the atlas pages now rather than when the slot is reused
+                if let Some(a) = self.glyph_atlases.get_mut(font.id) {
+                    *a = None;
+                }
+                for styled in self.style_fonts.remove(&font.id).unwrap_or_default() {
+                    self.unload(styled);
+                }
+                for styles in self.style_fonts.values_mut() {
+                    styles.retain(|f| *f != font);
+                }
+            },
+            FontKind::Bitmap => self.unload_bitmap(font),
+            FontKind::Family => match self.font_families.get_mut(font.id) {
+                Some(f @ Some(_)) => {
+                    *f = None;
+                    log::info!("unloaded font family, id: {}", font.id);
+                },
+                _ => log::warn!("tried to free already freed font family, id: {}", font.id),
+            },
+        }
+    }
+
     unload_resource!(
-        ///unloades the [Font] stored by the sdl2 context, it can no longer be used
-        ,unload, self, self.loaded_font_paths, self.fonts, font, Font, "font");
+        , fn unload_ttf, self, self.loaded_font_paths, self.fonts, font, Font, "font");
+
+    unload_resource!(
+        , fn unload_bitmap, self, self.loaded_bitmap_font_paths, self.bitmap_fonts, font, Font, "bitmap font");
     
     /// return a [TextObject] that can be passed to 'Camera' to draw to the screen
     ///
@@ -75,33 +203,219 @@ impl<'a, T: 'a> FontManager<'a, T> {
                          pos      : Vec2,
                          height   : f64,
                          parallax : Vec2) -> Result<TextObject, Error> {
-        if self.fonts[font.id].is_none() {
-            return Err(Error::MissingResource(String::from("Used a text with an unloaded font")));
-        }
-        let t = Self::gen_sdl2_texture(
-            text,
-            colour.to_sdl2_colour(),
-            self.fonts[font.id].as_ref().unwrap(),
-            self.texture_creator
-        )?;
+        let effects = TextEffects::default();
+        let (t, margin) = self.render_text(font, text, colour, &effects)?;
         //get dimensions before passing to check_and_push
         let tex_width = t.query().width;
         let tex_height = t.query().height;
         let index = load_resource_helper!(check_and_push(self.text_draws, Some(t)));
+        if self.text_sources.len() <= index {
+            self.text_sources.resize(index + 1, None);
+        }
+        self.text_sources[index] = Some(TextSource {
+            font: *font, text: text.to_string(), colour, effects, margin
+        });
         let text_resource = Text { id: index, width: tex_width, height: tex_height};
+        let mut rect = get_text_rect_from_height(
+            Vec2::new(
+                text_resource.width as f64,
+                text_resource.height as f64),
+            pos,
+            height);
+        if text.is_empty() {
+            rect.w = 0.0;
+        }
         Ok(TextObject::new(
             text_resource,
-            get_text_rect_from_height(
-                Vec2::new(
-                    text_resource.width as f64,
-                    text_resource.height as f64),
-                pos,
-                height),
+            rect,
             None,
             parallax, Colour::white()
         ))
     }
 
+    /// change the string shown by a [TextObject] made with `load_text_obj`,
+    /// keeping its position, height, parallax and colour
+    ///
+    /// The text is rendered into the same texture slot, so the object does not have to be
+    /// unloaded and loaded again. Nothing is rendered if the string has not changed.
+    /// An empty string gives the object no width, so nothing is drawn.
+    pub fn set_text(&mut self, text_obj: &mut TextObject, text: &str) -> Result<(), Error> {
+        let source = self.get_text_source(text_obj)?;
+        if source.text == text {
+            return Ok(());
+        }
+        self.rerender_text_obj(text_obj, TextSource { text: text.to_string(), ..source })
+    }
+
+    /// draw a shadow or outline with a [TextObject] made with `load_text_obj`,
+    /// keeping the size and position of its text
+    ///
+    /// The effects are rendered into the text's texture,
+    /// so the object's rect grows to fit them.
+    /// Setting `TextEffects::default()` removes any effects.
+    pub fn set_text_effects(&mut self, text_obj: &mut TextObject, effects: TextEffects) -> Result<(), Error> {
+        let source = self.get_text_source(text_obj)?;
+        if source.effects == effects {
+            return Ok(());
+        }
+        self.rerender_text_obj(text_obj, TextSource { effects, ..source })
+    }
+
+    fn get_text_source(&self, text_obj: &TextObject) -> Result<TextSource, Error> {
+        match self.text_sources.get(text_obj.get_texture().id) {
+            Some(Some(source)) => Ok(source.clone()),
+            _ => Err(Error::MissingResource(String::from("Used an unloaded text object"))),
+        }
+    }
+
+    /// render the text object's texture again into the same slot,
+    /// keeping the text where it was drawn before
+    fn rerender_text_obj(&mut self, text_obj: &mut TextObject, source: TextSource) -> Result<(), Error> {
+        let old = self.get_text_source(text_obj)?;
+        let tex = text_obj.get_texture();
+        let scale = text_obj.rect.h / tex.height as f64;
+        let pos = Vec2::new(text_obj.rect.x + old.margin[0] as f64 * scale,
+                            text_obj.rect.y + old.margin[1] as f64 * scale);
+        let height = (tex.height - old.margin[1] - old.margin[3]) as f64 * scale;
+        let (t, margin) = self.render_text(&source.font, &source.text, source.colour, &source.effects)?;
+        let empty = source.text.is_empty();
+        let text_resource = Text { id: tex.id, width: t.query().width, height: t.query().height };
+        self.text_draws[tex.id] = Some(t);
+        self.text_sources[tex.id] = Some(TextSource { margin, ..source });
+        text_obj.set_texture(text_resource);
+        let text_height = (text_resource.height - margin[1] - margin[3]) as f64;
+        let scale = height / text_height;
+        text_obj.rect = Rect::new(
+            pos.x - margin[0] as f64 * scale,
+            pos.y - margin[1] as f64 * scale,
+            text_resource.width as f64 * scale,
+            text_resource.height as f64 * scale);
+        if empty {
+            text_obj.rect.w = 0.0;
+        }
+        Ok(())
+    }
+
+    /// return a [TextObject] for each line of the text, arranged using the [TextLayout]
+    ///
+    /// Lines are wrapped and aligned within the layout's rect, with `height` as the height of each line.
+    /// Justified lines have a [TextObject] per word.
+    /// Each of the returned objects should be freed with `unload_text_obj` when no longer needed.
+    pub fn load_text_layout(&mut self,
+                            font     : &Font,
+                            text     : &str,
+                            colour   : Colour,
+                            height   : f64,
+                            layout   : &TextLayout,
+                            parallax : Vec2) -> Result<Vec<TextObject>, Error> {
+        self.text_width(font, "", height)?;
+        let pieces = layout.arrange(text, height, |s| self.text_width(font, s, height).unwrap_or(0.0));
+        let mut objs = Vec::new();
+        for (piece, pos) in pieces {
+            objs.push(self.load_text_obj(font, &piece, colour, pos, height, parallax)?);
+        }
+        Ok(objs)
+    }
+
+    /// return [TextObject]s for text with inline colour and style markup, see [parse_markup]
+    ///
+    /// The spans of text are placed one after another starting at `pos`,
+    /// with new lines for any line breaks.
+    /// Styled spans use the same font file as `font` loaded with that style,
+    /// which stays loaded until `font` is unloaded,
+    /// bitmap fonts and font families only use the colour of the spans.
+    /// Each of the returned objects should be freed with `unload_text_obj` when no longer needed.
+    pub fn load_rich_text(&mut self,
+                          font     : &Font,
+                          markup   : &str,
+                          colour   : Colour,
+                          pos      : Vec2,
+                          height   : f64,
+                          parallax : Vec2) -> Result<Vec<TextObject>, Error> {
+        self.text_width(font, "", height)?;
+        let mut objs = Vec::new();
+        let mut cursor = pos;
+        for span in parse_markup(markup, colour)? {
+            let span_font = match font.kind {
+                FontKind::Ttf => {
+                    let (path, settings) = self.font_sources[font.id].clone();
+                    let settings = FontSettings {
+                        bold: settings.bold || span.bold,
+                        italic: settings.italic || span.italic,
+                        underline: settings.underline || span.underline,
+                        strikethrough: settings.strikethrough || span.strikethrough,
+                        ..settings
+                    };
+                    let loaded = self.loaded_font_paths.contains_key(&settings.path_key(&path));
+                    let styled = self.load_font_with(Path::new(&path), &settings)?;
+                    if !loaded {
+                        self.style_fonts.entry(font.id).or_default().push(styled);
+                    }
+                    styled
+                },
+                _ => *font,
+            };
+            for (i, line) in span.text.split('\n').enumerate() {
+                if i > 0 {
+                    cursor = Vec2::new(pos.x, cursor.y + height);
+                }
+                // whitespace only has to move the cursor
+                if !line.trim().is_empty() {
+                    objs.push(self.load_text_obj(&span_font, line, span.colour, cursor, height, parallax)?);
+                }
+                cursor.x += self.text_width(&span_font, line, height)?;
+            }
+        }
+        Ok(objs)
+    }
+
+    /// get the size the text would be drawn at with the given height,
+    /// matching the size of a [TextObject] made with `load_text_obj`
+    pub fn measure(&self, font: &Font, text: &str, height: f64) -> Result<Vec2, Error> {
+        Ok(Vec2::new(self.text_width(font, text, height)?, height))
+    }
+
+    /// like `measure`, but also gets the caret positions between each character,
+    /// for placing a cursor in text or finding which character was clicked
+    pub fn measure_chars(&self, font: &Font, text: &str, height: f64) -> Result<TextMetrics, Error> {
+        let mut carets = vec![0.0];
+        for (run_font, run) in self.font_runs(font, text)? {
+            let start = *carets.last().unwrap();
+            match run_font.kind {
+                FontKind::Bitmap => {
+                    let bitmap = self.get_bitmap_font(&run_font)?;
+                    carets.extend(bitmap.carets(&run).iter().skip(1)
+                                  .map(|c| start + c * height / bitmap.line_height));
+                },
+                _ => {
+                    let sdl_font = self.get_font(&run_font)?;
+                    for (i, c) in run.char_indices() {
+                        carets.push(start + text_width(sdl_font, &run[..i + c.len_utf8()], height));
+                    }
+                },
+            }
+        }
+        Ok(TextMetrics {
+            size: Vec2::new(*carets.last().unwrap(), height),
+            carets,
+        })
+    }
+
+    /// the height of a line of text in the font when its glyphs are `pixel_size` tall
+    pub(crate) fn line_height(&self, font: &Font, pixel_size: f64) -> Result<f64, Error> {
+        match font.kind {
+            // bitmap fonts are drawn with their glyphs filling the line
+            FontKind::Bitmap => {
+                self.get_bitmap_font(font)?;
+                return Ok(pixel_size);
+            },
+            FontKind::Family => return self.line_height(&self.get_font_family(font)?[0], pixel_size),
+            FontKind::Ttf => (),
+        }
+        let line = self.get_font(font)?.height() as f64;
+        Ok(pixel_size * line / self.font_sources[font.id].1.size as f64)
+    }
+
     /// frees the texture stored and associated with the [TextObject],
     /// must not be used after freeing
     ///
@@ -111,6 +425,39 @@ impl<'a, T: 'a> FontManager<'a, T> {
     /// For example you can unload assets from one level and load in the next which switching levels.
     pub fn unload_text_obj(&mut self, text_obj: TextObject) {
         self.text_draws[text_obj.get_texture().id] = None;
+        self.text_sources[text_obj.get_texture().id] = None;
+    }
+
+    /// Get the fonts currently loaded, with their estimated memory use
+    ///
+    /// The size of a ttf font is estimated using the size of its file,
+    /// and a bitmap font using the size of its page textures
+    pub fn stats(&self) -> ResourceStats {
+        let mut stats = ResourceStats::from_paths(
+            "Font", &self.loaded_font_paths, |_, id| file_size(&self.font_sources[id].0));
+        for (path, id) in self.loaded_bitmap_font_paths.iter() {
+            if let Some(Some(f)) = self.bitmap_fonts.get(*id) {
+                stats.push(*id, path.clone(), f.bytes());
+            }
+        }
+        stats
+    }
+
+    /// Get the [TextObject] textures currently loaded, with their estimated gpu memory use
+    pub fn text_stats(&self) -> ResourceStats {
+        let mut stats = ResourceStats::new("Text");
+        for (id, t) in self.text_draws.iter().enumerate() {
+            if let Some(t) = t {
+                stats.push(id, "<text object>".to_string(),
+                           t.query().width as usize * t.query().height as usize * 4);
+            }
+        }
+        for (id, a) in self.glyph_atlases.iter().enumerate() {
+            if let Some(a) = a {
+                stats.push(id, format!("<glyph atlas of font {}>", id), a.bytes());
+            }
+        }
+        stats
     }
 
     pub(crate) fn new(ttf_context : &'a ttf::Sdl2TtfContext,
@@ -120,39 +467,77 @@ impl<'a, T: 'a> FontManager<'a, T> {
             ttf_context,
             loaded_font_paths: HashMap::new(),
             fonts : Vec::new(),
+            rendered_glyphs: RefCell::new(HashMap::new()),
+            font_sources: Vec::new(),
             text_draws: Vec::new(),
+            text_sources: Vec::new(),
+            glyph_atlases: Vec::new(),
+            loaded_bitmap_font_paths: HashMap::new(),
+            bitmap_fonts: Vec::new(),
+            font_families: Vec::new(),
+            style_fonts: HashMap::new(),
         }
     }
 
-    /// draws the supplied text to the canvas in the supplied font at the given height and position
-    fn draw(&self,
+    /// draws the supplied text to the canvas in the supplied font at the given position,
+    /// scaled so that a line of text fills the height of the rect
+    ///
+    /// glyphs are drawn from the font's [GlyphAtlas], or the pages of a bitmap font,
+    /// so no textures are created per string
+    fn draw(&mut self,
             canvas : &mut Canvas<Window>,
             font   : &Font,
             text   : &str,
-            height : u32,
             pos    : Vec2,
-            colour : Color,
+            colour : Colour,
             rect   : Rect) -> Result<(), Error> {
-        if text.len() == 0 { return Ok(()); }
-        let mut tex_draw = self.get_rendered_text(font, text, height, colour)?;
-        tex_draw.rect.x = pos.x as i32;
-        tex_draw.rect.y = pos.y as i32;
-        tex_draw.rect.h = rect.h as i32;
-        tex_draw.rect.w =
-            (tex_draw.rect.w as f64 *
-            (rect.w / height as f64)) as i32;
-        Ok(draw_err!(canvas.copy(&tex_draw.tex, None, tex_draw.rect))?)
-    }
-
-    pub(crate) fn draw_disposable(&self,
+        if text.is_empty() { return Ok(()); }
+        if font.kind == FontKind::Family {
+            let mut pos = pos;
+            for (run_font, run) in self.font_runs(font, text)? {
+                self.draw(canvas, &run_font, &run, pos, colour, rect)?;
+                pos.x += self.text_width(&run_font, &run, rect.w)?;
+            }
+            return Ok(());
+        }
+        if font.kind == FontKind::Bitmap {
+            let bitmap = match self.bitmap_fonts.get_mut(font.id) {
+                Some(Some(f)) => f,
+                _ => return Err(Error::MissingResource("Font has been unloaded".to_string())),
+            };
+            let line_height = bitmap.line_height;
+            return bitmap.draw(canvas, text, pos,
+                               Vec2::new(rect.w / line_height, rect.h / line_height), colour);
+        }
+        let sdl_font = match self.fonts.get(font.id) {
+            Some(Some(f)) => f,
+            _ => return Err(Error::MissingResource("Font has been unloaded".to_string())),
+        };
+        if self.glyph_atlases.len() <= font.id {
+            self.glyph_atlases.resize_with(font.id + 1, || None);
+        }
+        let atlas = self.glyph_atlases[font.id].get_or_insert_with(GlyphAtlas::new);
+        let line_height = sdl_font.height() as f64;
+        atlas.draw(canvas, sdl_font, self.texture_creator, text, pos,
+                   Vec2::new(rect.w / line_height, rect.h / line_height), colour)
+    }
+
+    pub(crate) fn draw_disposable(&mut self,
                                   canvas: &mut Canvas<Window>,
                                   disposable: DisposableTextDraw) -> Result<(), Error> {
+        for (offset, colour) in disposable.effects.copies(disposable.rect.h) {
+            self.draw(canvas,
+                      &disposable.font,
+                      &disposable.text,
+                      disposable.pos + offset,
+                      colour,
+                      disposable.rect)?;
+        }
         self.draw(canvas,
                   &disposable.font,
                   &disposable.text,
-                  disposable.height,
                   disposable.pos,
-                  disposable.colour.to_sdl2_colour(),
+                  disposable.colour,
                   disposable.rect
         )
     }
@@ -162,50 +547,165 @@ impl<'a, T: 'a> FontManager<'a, T> {
             self.text_draws
     }
     
-    fn get_rendered_text(&self,
-                         font: &Font,
-                         text: &str,
-                         height : u32,
-                         colour : Color) -> Result<ResourceTextDraw, Error> {
-        self.get_rendered_text_at_position(font, text, height, Vec2::new(0.0, 0.0), colour)
-    }
-
-    fn get_rendered_text_at_position(&self,
-                                     font: &Font,
-                                     text: &str,
-                                     height : u32,
-                                     pos: Vec2,
-                                     colour: Color) -> Result<ResourceTextDraw, Error> {
-        if text.len() == 0 {
-            return Err(Error::TextRender("text length should be greater than 0".to_string()));
-        }
-        if self.fonts[font.id].is_none() {
-            return Err(Error::MissingResource("Font has been unloaded".to_string()))
-        }
-        let tex = Self::gen_sdl2_texture(
-            text, colour, self.fonts[font.id].as_ref().unwrap(), self.texture_creator)?;
-        Ok(
-            ResourceTextDraw {
-                rect : Self::get_rect_from_sdl_texture(&tex, pos, height),
-                tex,
+    fn get_font(&self, font: &Font) -> Result<&ttf::Font<'a, 'static>, Error> {
+        match self.fonts.get(font.id) {
+            Some(Some(f)) => Ok(f),
+            _ => Err(Error::MissingResource(String::from("Used an unloaded font"))),
+        }
+    }
+
+    fn get_bitmap_font(&self, font: &Font) -> Result<&BitmapFont<'a>, Error> {
+        match self.bitmap_fonts.get(font.id) {
+            Some(Some(f)) => Ok(f),
+            _ => Err(Error::MissingResource(String::from("Used an unloaded font"))),
+        }
+    }
+
+    fn get_font_family(&self, font: &Font) -> Result<&Vec<Font>, Error> {
+        match self.font_families.get(font.id) {
+            Some(Some(f)) => Ok(f),
+            _ => Err(Error::MissingResource(String::from("Used an unloaded font family"))),
+        }
+    }
+
+    /// true if the ttf or bitmap font has a glyph for the character
+    fn has_glyph(&self, font: &Font, ch: char) -> bool {
+        match font.kind {
+            FontKind::Ttf => match self.get_font(font) {
+                Ok(f) if ch as u32 <= 0xFFFF => f.find_glyph(ch).is_some(),
+                Ok(f) => *self.rendered_glyphs.borrow_mut()
+                    .entry((font.id, ch))
+                    .or_insert_with(|| renders_glyph(f, ch)),
+                Err(_) => false,
+            },
+            FontKind::Bitmap => self.get_bitmap_font(font).is_ok_and(|f| f.has_glyph(ch)),
+            FontKind::Family => false,
+        }
+    }
+
+    /// split the text into pieces that are each drawn with one ttf or bitmap font,
+    /// a family uses the first of its fonts with a glyph for each character
+    fn font_runs(&self, font: &Font, text: &str) -> Result<Vec<(Font, String)>, Error> {
+        if font.kind != FontKind::Family {
+            return Ok(vec![(*font, text.to_string())]);
+        }
+        let family = self.get_font_family(font)?;
+        let mut runs: Vec<(Font, String)> = Vec::new();
+        for ch in text.chars() {
+            let f = *family.iter().find(|f| self.has_glyph(f, ch)).unwrap_or(&family[0]);
+            match runs.last_mut() {
+                Some((last, run)) if *last == f => run.push(ch),
+                _ => runs.push((f, ch.to_string())),
             }
-        )
+        }
+        Ok(runs)
     }
 
-    fn gen_sdl2_texture(text: &str,
+    /// the width of the text in any kind of font when a line is scaled to `height`
+    fn text_width(&self, font: &Font, text: &str, height: f64) -> Result<f64, Error> {
+        Ok(match font.kind {
+            FontKind::Ttf => text_width(self.get_font(font)?, text, height),
+            FontKind::Bitmap => {
+                let bitmap = self.get_bitmap_font(font)?;
+                bitmap.width(text) * height / bitmap.line_height
+            },
+            FontKind::Family => {
+                let mut width = 0.0;
+                for (run_font, run) in self.font_runs(font, text)? {
+                    width += self.text_width(&run_font, &run, height)?;
+                }
+                width
+            },
+        })
+    }
+
+    /// render the text to a new texture in the colour with any effects, with any kind of font,
+    /// also returning the space added around the text by the effects
+    fn render_text(&self, font: &Font, text: &str, colour: Colour, effects: &TextEffects)
+                   -> Result<(sdl2::render::Texture<'a>, [u32; 4]), Error> {
+        // sdl can't render an empty string or make a texture with no width,
+        // so empty text is rendered as a space and its object is given no width
+        let text = if text.is_empty() { " " } else { text };
+        let surface = self.render_surface(font, text, colour)?;
+        if effects.is_empty() {
+            return Ok((font_err!(self.texture_creator.create_texture_from_surface(&surface))?, [0; 4]));
+        }
+        let (pixels, margin) = effects.apply(&Pixels::from_surface(&surface)?);
+        let t = streaming_texture(self.texture_creator, pixels.width, pixels.height, &pixels.rgba)?;
+        Ok((t, margin))
+    }
+
+    fn render_surface(&self, font: &Font, text: &str, colour: Colour) -> Result<Surface<'static>, Error> {
+        match font.kind {
+            FontKind::Ttf => Self::gen_sdl2_surface(text, colour.to_sdl2_colour(), self.get_font(font)?),
+            FontKind::Bitmap => {
+                let (w, h, rgba) = self.get_bitmap_font(font)?.render(text, colour);
+                let mut surface = font_err!(Surface::new(w, h, PixelFormatEnum::RGBA32))?;
+                let pitch = surface.pitch() as usize;
+                surface.with_lock_mut(|data| {
+                    for row in 0..h as usize {
+                        let src = row * w as usize * 4;
+                        data[row * pitch..row * pitch + w as usize * 4]
+                            .copy_from_slice(&rgba[src..src + w as usize * 4]);
+                    }
+                });
+                Ok(surface)
+            },
+            FontKind::Family => {
+                // each run is scaled so its line is as tall as a line of the first font
+                let runs = self.font_runs(font, text)?;
+                if runs.is_empty() {
+                    return self.render_surface(&self.get_font_family(font)?[0], text, colour);
+                }
+                let mut surfaces = Vec::new();
+                for (run_font, run) in runs.iter() {
+                    surfaces.push(self.render_surface(run_font, run, colour)?);
+                }
+                let height = surfaces[0].height().max(1);
+                let widths: Vec<u32> = surfaces.iter()
+                    .map(|s| (s.width() as u64 * height as u64 / s.height().max(1) as u64) as u32)
+                    .collect();
+                let mut out = font_err!(Surface::new(
+                    widths.iter().sum::<u32>().max(1), height, PixelFormatEnum::RGBA32))?;
+                let mut x = 0;
+                for (mut s, w) in surfaces.into_iter().zip(widths) {
+                    font_err!(s.set_blend_mode(BlendMode::None))?;
+                    font_err!(s.blit_scaled(None, &mut out, sdl2::rect::Rect::new(x, 0, w.max(1), height)))?;
+                    x += w as i32;
+                }
+                Ok(out)
+            },
+        }
+    }
+
+    fn gen_sdl2_surface(text: &str,
                         colour : Color,
-                        font: &ttf::Font<'a, 'static>,
-                        texture_creator : &'a TextureCreator<T>)
-                        -> Result<sdl2::render::Texture<'a>, Error> {
-        let surface = font_err!(font.render(text).blended(colour))?;
-        Ok(font_err!(texture_creator.create_texture_from_surface(&surface))?)
+                        font: &ttf::Font<'a, 'static>) -> Result<Surface<'static>, Error> {
+        Ok(font_err!(font.render(text).blended(colour))?)
     }
     
-    fn get_rect_from_sdl_texture(tex: &sdl2::render::Texture, pos: Vec2, height : u32)
-                                 -> sdl2::rect::Rect {
-        let dim  = Vec2::new(tex.query().width as f64, tex.query().height as f64);
-        let rect = get_text_rect_from_height(dim, pos, height as f64);
-        rect.to_sdl_rect()
+}
+
+/// sdl2 looks glyphs up by a u16, so characters above `U+FFFF` are checked by rendering them
+/// and comparing against a character that no font has, which renders as the missing glyph
+fn renders_glyph(font: &ttf::Font, ch: char) -> bool {
+    let render = |c: char| font.render_char(c).blended(Color::WHITE).ok()
+        .and_then(|s| Pixels::from_surface(&s).ok());
+    match (render(ch), render('\u{10FFFF}')) {
+        (Some(glyph), Some(missing)) => glyph.width != missing.width || glyph.rgba != missing.rgba,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// the width of the text when a line of the font is scaled to `height`
+fn text_width(font: &ttf::Font, text: &str, height: f64) -> f64 {
+    if text.is_empty() {
+        return 0.0;
+    }
+    match font.size_of(text) {
+        Ok((w, h)) if h > 0 => w as f64 * height / h as f64,
+        _ => 0.0,
     }
 }
 
@@ -0,0 +1,70 @@
+use sdl2::ttf;
+
+/// How the outlines of glyphs are fitted to the pixel grid when rendering a font
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FontHinting {
+    Normal,
+    Light,
+    /// sharpest hinting, good for small pixel fonts
+    Mono,
+    None,
+}
+
+/// Options for loading a font with [crate::manager::FontManager::load_font_with]
+///
+/// The same font file loaded with different settings is held as a separate font.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FontSettings {
+    /// the pixel size the glyphs are rendered at, text is scaled from this size when drawn.
+    /// Pixel fonts look crisp when loaded at their native size and drawn at multiples of it.
+    pub size: u16,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// thickness in pixels of an outline drawn instead of the filled glyphs, `0` for no outline
+    pub outline: u16,
+    pub hinting: FontHinting,
+}
+
+impl Default for FontSettings {
+    fn default() -> Self {
+        FontSettings {
+            size: super::font_manager::FONT_LOAD_SIZE,
+            bold: false,
+            italic: false,
+            underline: false,
+            strikethrough: false,
+            outline: 0,
+            hinting: FontHinting::Normal,
+        }
+    }
+}
+
+impl FontSettings {
+    pub(crate) fn apply(&self, font: &mut ttf::Font) {
+        let mut style = ttf::FontStyle::NORMAL;
+        if self.bold { style |= ttf::FontStyle::BOLD; }
+        if self.italic { style |= ttf::FontStyle::ITALIC; }
+        if self.underline { style |= ttf::FontStyle::UNDERLINE; }
+        if self.strikethrough { style |= ttf::FontStyle::STRIKETHROUGH; }
+        font.set_style(style);
+        font.set_outline_width(self.outline);
+        font.set_hinting(match self.hinting {
+            FontHinting::Normal => ttf::Hinting::Normal,
+            FontHinting::Light => ttf::Hinting::Light,
+            FontHinting::Mono => ttf::Hinting::Mono,
+            FontHinting::None => ttf::Hinting::None,
+        });
+    }
+
+    /// a key for a font file loaded with these settings,
+    /// the default settings use just the path so that `load_font` finds the same font
+    pub(crate) fn path_key(&self, path: &str) -> String {
+        if *self == Self::default() {
+            path.to_string()
+        } else {
+            format!("{} {:?}", path, self)
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use sdl2::render::{TextureCreator, Texture, Canvas};
+use sdl2::{video::Window, pixels::{Color, PixelFormatEnum}, ttf};
+
+use std::collections::HashMap;
+
+use crate::{Colour, Error, font_err, draw_err};
+use crate::resources::texture_manager::streaming_texture;
+use crate::geometry::*;
+
+const PAGE_SIZE: u32 = 1024;
+
+/// A glyph rasterised into one of the atlas pages
+#[derive(Clone, Copy)]
+struct Glyph {
+    page: usize,
+    rect: Option<sdl2::rect::Rect>,
+    x_offset: i32,
+    advance: i32,
+}
+
+/// Caches the glyphs of a font in large textures, so that text can be drawn
+/// one quad per character without creating a new texture for each string.
+///
+/// Glyphs are rasterised in white the first time they are drawn,
+/// and the draw colour is applied with a colour mod.
+pub(crate) struct GlyphAtlas<'a> {
+    pages: Vec<Texture<'a>>,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), i32>,
+    /// the page new glyphs are packed into, glyphs too big for a page get their own
+    cursor_page: Option<usize>,
+    cursor_x: u32,
+    cursor_y: u32,
+    row_height: u32,
+}
+
+impl<'a> GlyphAtlas<'a> {
+    pub fn new() -> Self {
+        GlyphAtlas {
+            pages: Vec::new(),
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+            cursor_page: None,
+            cursor_x: 0,
+            cursor_y: 0,
+            row_height: 0,
+        }
+    }
+
+    /// draw the text with its top left at `pos`,
+    /// with `scale` converting from the font's pixels to the canvas' pixels
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw<T>(&mut self,
+                   canvas: &mut Canvas<Window>,
+                   font: &ttf::Font,
+                   texture_creator: &'a TextureCreator<T>,
+                   text: &str,
+                   pos: Vec2,
+                   scale: Vec2,
+                   colour: Colour) -> Result<(), Error> {
+        let mut pen = 0;
+        let mut prev: Option<char> = None;
+        for ch in text.chars() {
+            if ch.is_control() {
+                continue;
+            }
+            if let Some(p) = prev {
+                pen += self.kerning(font, p, ch);
+            }
+            let glyph = self.glyph(font, texture_creator, ch)?;
+            if let Some(r) = glyph.rect {
+                let page = &mut self.pages[glyph.page];
+                page.set_color_mod(colour.r, colour.g, colour.b);
+                page.set_alpha_mod(colour.a);
+                let dst = Rect::new(
+                    pos.x + (pen + glyph.x_offset) as f64 * scale.x,
+                    pos.y,
+                    r.width() as f64 * scale.x,
+                    r.height() as f64 * scale.y);
+                draw_err!(canvas.copy(page, r, sdl2::rect::Rect::new(
+                    dst.x as i32, dst.y as i32, dst.w.ceil() as u32, dst.h.ceil() as u32)))?;
+            }
+            pen += glyph.advance;
+            prev = Some(ch);
+        }
+        Ok(())
+    }
+
+    /// the extra space between two characters when they are next to each other
+    fn kerning(&mut self, font: &ttf::Font, a: char, b: char) -> i32 {
+        *self.kerning.entry((a, b)).or_insert_with(|| {
+            // the sdl2 bindings don't expose kerning pairs,
+            // so compare the width of the pair to the widths of each character
+            let width = |s: &str| font.size_of(s).map(|(w, _)| w as i32).unwrap_or(0);
+            let pair: String = [a, b].iter().collect();
+            width(&pair) - width(&a.to_string()) - width(&b.to_string())
+        })
+    }
+
+    fn glyph<T>(&mut self,
+                font: &ttf::Font,
+                texture_creator: &'a TextureCreator<T>,
+                ch: char) -> Result<Glyph, Error> {
+        if let Some(g) = self.glyphs.get(&ch) {
+            return Ok(*g);
+        }
+        // sdl2 looks glyphs up by a u16, so characters above U+FFFF are measured as text instead
+        let metrics = if ch as u32 <= 0xFFFF { font.find_glyph_metrics(ch) } else { None };
+        let advance = match &metrics {
+            Some(m) => m.advance,
+            None => font.size_of_char(ch).map(|(w, _)| w as i32).unwrap_or(0),
+        };
+        let x_offset = metrics.map(|m| m.minx.min(0)).unwrap_or(0);
+        let surface = match font.render_char(ch).blended(Color::WHITE) {
+            Ok(s) => Some(font_err!(s.convert_format(PixelFormatEnum::RGBA32))?),
+            // characters with nothing to draw, like spaces, may fail to render
+            Err(_) => None,
+        };
+        let mut glyph = Glyph { page: 0, rect: None, x_offset, advance };
+        if let Some(s) = surface {
+            if s.width() > 0 && s.height() > 0 {
+                let (page, rect) = self.allocate(texture_creator, s.width(), s.height())?;
+                glyph.page = page;
+                let pitch = s.pitch() as usize;
+                s.with_lock(|data| self.pages[glyph.page].update(rect, data, pitch))
+                    .map_err(|e| Error::TextRender(e.to_string()))?;
+                glyph.rect = Some(rect);
+            }
+        }
+        self.glyphs.insert(ch, glyph);
+        Ok(glyph)
+    }
+
+    /// find space for a glyph, returning the page and the rect within it,
+    /// starting a new page if the current one is full
+    fn allocate<T>(&mut self,
+                   texture_creator: &'a TextureCreator<T>,
+                   w: u32, h: u32) -> Result<(usize, sdl2::rect::Rect), Error> {
+        if w > PAGE_SIZE || h > PAGE_SIZE {
+            // glyphs of very large fonts get a page sized to fit them
+            let page = self.add_page(texture_creator, w, h)?;
+            return Ok((page, sdl2::rect::Rect::new(0, 0, w, h)));
+        }
+        if self.cursor_x + w > PAGE_SIZE {
+            self.cursor_x = 0;
+            self.cursor_y += self.row_height;
+            self.row_height = 0;
+        }
+        let page = match self.cursor_page {
+            Some(page) if self.cursor_y + h <= PAGE_SIZE => page,
+            _ => {
+                let page = self.add_page(texture_creator, PAGE_SIZE, PAGE_SIZE)?;
+                self.cursor_page = Some(page);
+                self.cursor_x = 0;
+                self.cursor_y = 0;
+                self.row_height = 0;
+                page
+            }
+        };
+        let rect = sdl2::rect::Rect::new(self.cursor_x as i32, self.cursor_y as i32, w, h);
+        self.cursor_x += w;
+        self.row_height = self.row_height.max(h);
+        Ok((page, rect))
+    }
+
+    /// create a blank page and return its index
+    fn add_page<T>(&mut self,
+                   texture_creator: &'a TextureCreator<T>,
+                   w: u32, h: u32) -> Result<usize, Error> {
+        // streaming textures start with undefined pixels
+        let blank = vec![0; (w * h * 4) as usize];
+        self.pages.push(streaming_texture(texture_creator, w, h, &blank)?);
+        Ok(self.pages.len() - 1)
+    }
+
+    /// estimated gpu memory used by the atlas pages
+    pub fn bytes(&self) -> usize {
+        self.pages.iter().map(|p| {
+            let q = p.query();
+            (q.width * q.height * 4) as usize
+        }).sum()
+    }
+}
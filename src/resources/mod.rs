@@ -4,3 +4,14 @@ pub mod resource;
 pub mod resource_macros;
 pub mod types;
 pub mod audio;
+pub mod pixels;
+pub mod palette;
+pub mod stats;
+pub mod glyph_atlas;
+pub mod text_layout;
+pub mod text_metrics;
+pub mod font_settings;
+pub mod rich_text;
+pub mod bitmap_font;
+pub mod text_effects;
+pub mod random;
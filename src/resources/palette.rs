@@ -0,0 +1,74 @@
+use crate::{Colour, Error, Pixels};
+
+/// A set of colour replacements used by
+/// [crate::manager::TextureManager::palette_swap] to make recoloured textures.
+///
+/// Colours are matched on their red, green and blue values.
+/// The replacement keeps the alpha of the original pixel, scaled by the alpha of the new colour.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Palette {
+    swaps: Vec<(Colour, Colour)>,
+    fill: Option<Colour>,
+}
+
+impl Palette {
+    /// a palette that makes no changes
+    pub fn new() -> Palette {
+        Palette { swaps: Vec::new(), fill: None }
+    }
+
+    /// a palette that replaces every visible pixel with one colour,
+    /// ie `Palette::silhouette(Colour::white())` for hit flashes
+    pub fn silhouette(colour: Colour) -> Palette {
+        Palette { swaps: Vec::new(), fill: Some(colour) }
+    }
+
+    /// build a palette from two rows of a palette image,
+    /// each colour in `from_row` is replaced by the colour in the same column of `to_row`
+    pub fn from_rows(pixels: &Pixels, from_row: u32, to_row: u32) -> Result<Palette, Error> {
+        if from_row >= pixels.height || to_row >= pixels.height {
+            return Err(Error::TextureCreate(format!(
+                "palette rows {} and {} must be less than the image height {}",
+                from_row, to_row, pixels.height)));
+        }
+        let mut palette = Palette::new();
+        for x in 0..pixels.width {
+            palette = palette.swap(
+                pixels.get(x, from_row).unwrap(),
+                pixels.get(x, to_row).unwrap());
+        }
+        Ok(palette)
+    }
+
+    /// add a replacement of the `from` colour with the `to` colour,
+    /// replacing any earlier swap of `from`
+    pub fn swap(mut self, from: Colour, to: Colour) -> Palette {
+        self.swaps.retain(|(f, _)| !same_rgb(f, &from));
+        self.swaps.push((from, to));
+        self
+    }
+
+    /// apply this palette to a pixel buffer in place
+    pub fn apply(&self, rgba: &mut [u8]) {
+        for p in rgba.chunks_exact_mut(4) {
+            if p[3] == 0 {
+                continue;
+            }
+            let colour = Colour::new(p[0], p[1], p[2], p[3]);
+            let new = match self.fill {
+                Some(c) => Some(c),
+                None => self.swaps.iter().find(|(f, _)| same_rgb(f, &colour)).map(|(_, t)| *t),
+            };
+            if let Some(c) = new {
+                p[0] = c.r;
+                p[1] = c.g;
+                p[2] = c.b;
+                p[3] = ((p[3] as u32 * c.a as u32) / 255) as u8;
+            }
+        }
+    }
+}
+
+fn same_rgb(a: &Colour, b: &Colour) -> bool {
+    a.r == b.r && a.g == b.g && a.b == b.b
+}
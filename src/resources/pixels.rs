@@ -0,0 +1,267 @@
+use std::path::Path;
+use sdl2::{image::LoadSurface, pixels::PixelFormatEnum, surface::Surface};
+
+use crate::{Colour, Error, GameObject, file_err};
+use crate::geometry::*;
+
+/// A copy of an image's pixels held in normal memory,
+/// with 4 bytes per pixel in the order red, green, blue, alpha.
+///
+/// Returned by [crate::manager::TextureManager::load_with_pixels]
+#[derive(Clone)]
+pub struct Pixels {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl Pixels {
+    /// load an image file into memory without creating a texture
+    pub fn load(path: &Path) -> Result<Pixels, Error> {
+        Self::from_surface(&file_err!(Surface::from_file(path))?)
+    }
+
+    /// copy the pixels of an sdl2 surface
+    pub(crate) fn from_surface(surface: &Surface) -> Result<Pixels, Error> {
+        let surface = file_err!(surface.convert_format(PixelFormatEnum::RGBA32))?;
+        let (width, height, pitch) = (surface.width(), surface.height(), surface.pitch() as usize);
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        surface.with_lock(|data| {
+            for row in 0..height as usize {
+                let start = row * pitch;
+                rgba.extend_from_slice(&data[start..start + width as usize * 4]);
+            }
+        });
+        Ok(Pixels { width, height, rgba })
+    }
+
+    /// get the colour of the pixel at `x`, `y`, or `None` if it is outside of the image
+    pub fn get(&self, x: u32, y: u32) -> Option<Colour> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        Some(Colour::new(self.rgba[i], self.rgba[i + 1], self.rgba[i + 2], self.rgba[i + 3]))
+    }
+}
+
+/// Marks which pixels of a texture are solid, for pixel perfect collision tests
+/// between [GameObject]s.
+///
+/// Build one from the [Pixels] of the same image the [GameObject]'s texture was loaded from.
+#[derive(Clone)]
+pub struct CollisionMask {
+    width: u32,
+    height: u32,
+    solid: Vec<bool>,
+}
+
+impl CollisionMask {
+    /// create a mask where any pixel with an alpha above zero is solid
+    pub fn new(pixels: &Pixels) -> CollisionMask {
+        Self::new_with_threshold(pixels, 0)
+    }
+
+    /// create a mask where pixels with an alpha greater than `alpha_threshold` are solid
+    pub fn new_with_threshold(pixels: &Pixels, alpha_threshold: u8) -> CollisionMask {
+        CollisionMask {
+            width: pixels.width,
+            height: pixels.height,
+            solid: pixels.rgba.chunks_exact(4).map(|p| p[3] > alpha_threshold).collect(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// returns true if the pixel at `x`, `y` is solid, pixels outside of the mask are never solid
+    pub fn is_solid(&self, x: i64, y: i64) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return false;
+        }
+        self.solid[y as usize * self.width as usize + x as usize]
+    }
+
+    /// check if the solid pixels of two [GameObject]s overlap
+    ///
+    /// Each object's `rect`, `tex_rect` and flips are used to find which pixel of its mask
+    /// is drawn at each point where the two rects intersect.
+    ///
+    /// Note: the objects' `rotate` is ignored
+    pub fn overlapping(obj: &GameObject, mask: &CollisionMask,
+                       other: &GameObject, other_mask: &CollisionMask) -> bool {
+        let area = match intersection(&obj.rect, &other.rect) {
+            Some(r) => r,
+            None => return false,
+        };
+        // step by the smallest drawn pixel size, so no pixel of either object is skipped
+        let (obj_step, other_step) = (mask.pixel_size(obj), other_mask.pixel_size(other));
+        let step = Vec2::new(obj_step.x.min(other_step.x), obj_step.y.min(other_step.y));
+        if step.x <= 0.0 || step.y <= 0.0 {
+            return false;
+        }
+        let mut y = area.y + step.y / 2.0;
+        while y < area.y + area.h {
+            let mut x = area.x + step.x / 2.0;
+            while x < area.x + area.w {
+                let point = Vec2::new(x, y);
+                if mask.solid_at(obj, point) && other_mask.solid_at(other, point) {
+                    return true;
+                }
+                x += step.x;
+            }
+            y += step.y;
+        }
+        false
+    }
+
+    /// check if the point, in the same space as the object's rect, lands on a solid pixel
+    pub fn solid_at(&self, obj: &GameObject, point: Vec2) -> bool {
+        if !obj.rect.contains(&point) {
+            return false;
+        }
+        let tex_rect = self.tex_rect(obj);
+        let mut u = (point.x - obj.rect.x) / obj.rect.w;
+        let mut v = (point.y - obj.rect.y) / obj.rect.h;
+        if obj.flip_horizontal { u = 1.0 - u; }
+        if obj.flip_vertical { v = 1.0 - v; }
+        self.is_solid(
+            (tex_rect.x + u * tex_rect.w).floor() as i64,
+            (tex_rect.y + v * tex_rect.h).floor() as i64,
+        )
+    }
+
+    fn tex_rect(&self, obj: &GameObject) -> Rect {
+        match obj.tex_rect {
+            Some(r) => r,
+            None => Rect::new(0.0, 0.0, self.width as f64, self.height as f64),
+        }
+    }
+
+    fn pixel_size(&self, obj: &GameObject) -> Vec2 {
+        let tex_rect = self.tex_rect(obj);
+        Vec2::new(obj.rect.w / tex_rect.w, obj.rect.h / tex_rect.h)
+    }
+}
+
+fn intersection(a: &Rect, b: &Rect) -> Option<Rect> {
+    if !a.colliding(b) {
+        return None;
+    }
+    let top_left = Vec2::new(a.x.max(b.x), a.y.max(b.y));
+    let bottom_right = Vec2::new((a.x + a.w).min(b.x + b.w), (a.y + a.h).min(b.y + b.h));
+    Some(Rect::new_from_vec2s(&top_left, &bottom_right))
+}
+
+/// alpha blend the source colour over the destination pixel, both with straight alpha
+pub(crate) fn blend_over(dst: &mut [u8], src: [u8; 4]) {
+    let sa = src[3] as u32;
+    if sa == 0 {
+        return;
+    }
+    let da = dst[3] as u32;
+    let out_a = sa + da * (255 - sa) / 255;
+    for c in 0..3 {
+        dst[c] = ((src[c] as u32 * sa + dst[c] as u32 * da * (255 - sa) / 255) / out_a) as u8;
+    }
+    dst[3] = out_a as u8;
+}
+
+#[cfg(test)]
+mod pixels_tests {
+    use super::*;
+    use crate::resource;
+
+    /// a mask where `#` is a solid pixel and anything else is clear
+    fn mask(rows: &[&str]) -> CollisionMask {
+        let rgba = rows.iter()
+            .flat_map(|r| r.chars())
+            .flat_map(|c| [255, 255, 255, if c == '#' { 255 } else { 0 }])
+            .collect();
+        CollisionMask::new(&Pixels { width: rows[0].len() as u32, height: rows.len() as u32, rgba })
+    }
+
+    fn obj(mask: &CollisionMask, rect: Rect, tex_rect: Option<Rect>) -> GameObject {
+        let texture = resource::Texture { id: 0, width: mask.width(), height: mask.height() };
+        GameObject::new(texture, rect, tex_rect, Vec2::new(1.0, 1.0), Colour::white())
+    }
+
+    #[test]
+    fn test_threshold() {
+        let pixels = Pixels { width: 3, height: 1, rgba: vec![0, 0, 0, 0, 0, 0, 0, 100, 0, 0, 0, 200] };
+        let m = CollisionMask::new_with_threshold(&pixels, 100);
+        assert!(!m.is_solid(0, 0) && !m.is_solid(1, 0) && m.is_solid(2, 0));
+        assert!(!m.is_solid(-1, 0) && !m.is_solid(3, 0));
+    }
+
+    #[test]
+    fn test_flips() {
+        let m = mask(&["#."]);
+        let mut o = obj(&m, Rect::new(0.0, 0.0, 2.0, 1.0), None);
+        assert!(m.solid_at(&o, Vec2::new(0.5, 0.5)));
+        assert!(!m.solid_at(&o, Vec2::new(1.5, 0.5)));
+        o.flip_horizontal = true;
+        assert!(!m.solid_at(&o, Vec2::new(0.5, 0.5)));
+        assert!(m.solid_at(&o, Vec2::new(1.5, 0.5)));
+
+        let m = mask(&["#", "."]);
+        let mut o = obj(&m, Rect::new(0.0, 0.0, 1.0, 2.0), None);
+        assert!(m.solid_at(&o, Vec2::new(0.5, 0.5)));
+        o.flip_vertical = true;
+        assert!(!m.solid_at(&o, Vec2::new(0.5, 0.5)));
+        assert!(m.solid_at(&o, Vec2::new(0.5, 1.5)));
+    }
+
+    #[test]
+    fn test_tex_rect() {
+        let m = mask(&["..#.", "...."]);
+        let o = obj(&m, Rect::new(10.0, 0.0, 2.0, 1.0), Some(Rect::new(2.0, 0.0, 2.0, 1.0)));
+        assert!(m.solid_at(&o, Vec2::new(10.5, 0.5)));
+        assert!(!m.solid_at(&o, Vec2::new(11.5, 0.5)));
+        // outside of the draw rect is never solid
+        assert!(!m.solid_at(&o, Vec2::new(9.5, 0.5)));
+    }
+
+    #[test]
+    fn test_scaled_rect() {
+        let m = mask(&["#.", ".."]);
+        let o = obj(&m, Rect::new(0.0, 0.0, 20.0, 20.0), None);
+        assert!(m.solid_at(&o, Vec2::new(9.0, 9.0)));
+        assert!(!m.solid_at(&o, Vec2::new(11.0, 9.0)));
+        assert!(!m.solid_at(&o, Vec2::new(9.0, 11.0)));
+
+        let big = mask(&["#"]);
+        let a = obj(&big, Rect::new(0.0, 0.0, 10.0, 10.0), None);
+        let small = mask(&["..", ".#"]);
+        let b = obj(&small, Rect::new(5.0, 5.0, 2.0, 2.0), None);
+        assert!(CollisionMask::overlapping(&a, &big, &b, &small));
+        // only the clear pixels of the small object are over the big one
+        let b = obj(&small, Rect::new(9.5, 9.5, 2.0, 2.0), None);
+        assert!(!CollisionMask::overlapping(&a, &big, &b, &small));
+    }
+
+    #[test]
+    fn test_overlapping_clear_pixels() {
+        let left = mask(&["#."]);
+        let right = mask(&[".#"]);
+        let a = obj(&left, Rect::new(0.0, 0.0, 2.0, 1.0), None);
+        let mut b = obj(&right, Rect::new(0.0, 0.0, 2.0, 1.0), None);
+        assert!(!CollisionMask::overlapping(&a, &left, &b, &right));
+        b.flip_horizontal = true;
+        assert!(CollisionMask::overlapping(&a, &left, &b, &right));
+    }
+
+    #[test]
+    fn test_rects_apart() {
+        let m = mask(&["##", "##"]);
+        let a = obj(&m, Rect::new(0.0, 0.0, 2.0, 2.0), None);
+        let b = obj(&m, Rect::new(5.0, 5.0, 2.0, 2.0), None);
+        assert!(!CollisionMask::overlapping(&a, &m, &b, &m));
+        assert!(CollisionMask::overlapping(&a, &m, &a, &m));
+    }
+}
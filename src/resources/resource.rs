@@ -15,9 +15,18 @@ pub struct Texture {
 pub type Text = Texture;
 
 /// A handle for a font loaded to memory and owned by [crate::manager::FontManager]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Font {
     pub(crate) id : usize,
+    pub(crate) kind: FontKind,
+}
+
+/// whether a [Font] is a ttf font, a bitmap font or a family of fallback fonts
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FontKind {
+    Ttf,
+    Bitmap,
+    Family,
 }
 
 /// can be returned by [crate::manager::SfxManager], links to a sound effect held by the manager
@@ -26,6 +35,24 @@ pub struct SoundEffect {
     pub(crate) id: usize,
 }
 
+/// returned by [crate::manager::SfxManager] when a sound effect is played,
+/// links to that one playback of the sound effect
+///
+/// once the playback has finished, using the handle does nothing,
+/// even if the channel it played on is playing something else
+#[derive(Clone, Copy)]
+pub struct SfxPlayback {
+    pub(crate) channel: i32,
+    pub(crate) generation: u64,
+}
+
+impl SfxPlayback {
+    /// a playback of a sound that was not played
+    pub(crate) fn skipped() -> SfxPlayback {
+        SfxPlayback { channel: -1, generation: 0 }
+    }
+}
+
 /// can be returned by [crate::manager::MusicManager], links to a music held by the manager
 #[derive(Clone, Copy)]
 pub struct Music {
@@ -85,9 +85,9 @@ macro_rules! unload_resource{
     // res      - a resource to unload
     // res_type - type of resource
     // name     - name of the resource type
-    ($(#[$($attrss:tt)*])*, $fn:ident, $s:ident, $path_map:expr, $res_list:expr, $res:ident, $res_type:ty , $name:expr) => {
+    ($(#[$($attrss:tt)*])*, $vis:vis fn $fn:ident, $s:ident, $path_map:expr, $res_list:expr, $res:ident, $res_type:ty , $name:expr) => {
         $(#[$($attrss)*])*
-        pub fn $fn(&mut $s, $res: $res_type) {
+        $vis fn $fn(&mut $s, $res: $res_type) {
 	    let mut loaded_path : Option<String> = None;
             for (k, v) in $path_map.iter() {
                 if *v == $res.id {
@@ -98,15 +98,19 @@ macro_rules! unload_resource{
             let loaded_path = match loaded_path {
                 Some(s) => s,
                 None => {
-                    println!("warning: tried to free already freed {}, id: {}", $name, $res.id);
+                    log::warn!("tried to free already freed {}, id: {}", $name, $res.id);
                     return;
                 },
             };
             $path_map.remove(&loaded_path);
             $res_list[$res.id] = None;
-            println!("unloaded {}, id: {}", $name, $res.id);
+            log::info!("unloaded {}, id: {}", $name, $res.id);
         }
     };
+    ($(#[$($attrss:tt)*])*, $fn:ident, $s:ident, $path_map:expr, $res_list:expr, $res:ident, $res_type:ty , $name:expr) => {
+        $crate::unload_resource!(
+            $(#[$($attrss)*])*, pub fn $fn, $s, $path_map, $res_list, $res, $res_type, $name);
+    };
 }
 
 //helper for load!
@@ -147,7 +151,7 @@ macro_rules! load_resource_helper {
     ($res_list: expr, $res_paths: expr, $res_name: expr, $ind: ident, $res:ident, $path:ident, $path_as_string:ident) => {{
             let $ind = $crate::load_resource_helper!(push_resource($res_list, $ind, $res));
             $res_paths.insert($path_as_string, $ind);
-            println!("loaded {} - id: {} - path: {}", $res_name, $ind, $path.to_str().unwrap());
+            log::info!("loaded {} - id: {} - path: {}", $res_name, $ind, $path.to_string_lossy());
             Ok($ind)
         }
     };
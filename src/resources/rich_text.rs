@@ -0,0 +1,179 @@
+use crate::{Colour, Error};
+
+/// A piece of text that has the same colour and style throughout,
+/// made by [parse_markup]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TextSpan {
+    pub text: String,
+    pub colour: Colour,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+/// Split text with inline markup into [TextSpan]s
+///
+/// Supported tags are:
+/// - `[c=#rrggbb]` or `[c=#rrggbbaa]` ... `[/c]` to colour text
+/// - `[b]` ... `[/b]` for bold
+/// - `[i]` ... `[/i]` for italic
+/// - `[u]` ... `[/u]` for underline
+/// - `[s]` ... `[/s]` for strikethrough
+///
+/// Tags can be nested, and `[[` is a literal `[`.
+/// Text outside of any colour tag uses `colour`.
+///
+/// Returns a `TextRender` error for unknown tags or closing tags without an opening tag,
+/// tags left open apply until the end of the text
+pub fn parse_markup(markup: &str, colour: Colour) -> Result<Vec<TextSpan>, Error> {
+    let mut spans: Vec<TextSpan> = Vec::new();
+    let mut colours = vec![colour];
+    let (mut bold, mut italic, mut underline, mut strikethrough) = (0, 0, 0, 0);
+    let mut text = String::new();
+    let mut chars = markup.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '[' {
+            text.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'[') {
+            chars.next();
+            text.push('[');
+            continue;
+        }
+        let mut tag = String::new();
+        loop {
+            match chars.next() {
+                Some(']') => break,
+                Some(c) => tag.push(c),
+                None => return Err(markup_err("tag was not closed with ]", &tag)),
+            }
+        }
+        // finish the text in the current style before changing it
+        if !text.is_empty() {
+            spans.push(TextSpan {
+                text: std::mem::take(&mut text),
+                colour: *colours.last().unwrap(),
+                bold: bold > 0,
+                italic: italic > 0,
+                underline: underline > 0,
+                strikethrough: strikethrough > 0,
+            });
+        }
+        let closing = tag.starts_with('/');
+        let counter = match tag.trim_start_matches('/') {
+            "b" => &mut bold,
+            "i" => &mut italic,
+            "u" => &mut underline,
+            "s" => &mut strikethrough,
+            "c" if closing => {
+                if colours.len() == 1 {
+                    return Err(markup_err("closing tag without an opening tag", &tag));
+                }
+                colours.pop();
+                continue;
+            },
+            t if t.starts_with("c=") && !closing => {
+                colours.push(parse_colour(&t[2..]).ok_or_else(|| markup_err("invalid colour", &tag))?);
+                continue;
+            },
+            _ => return Err(markup_err("unknown tag", &tag)),
+        };
+        if closing {
+            if *counter == 0 {
+                return Err(markup_err("closing tag without an opening tag", &tag));
+            }
+            *counter -= 1;
+        } else {
+            *counter += 1;
+        }
+    }
+    if !text.is_empty() {
+        spans.push(TextSpan {
+            text,
+            colour: *colours.last().unwrap(),
+            bold: bold > 0,
+            italic: italic > 0,
+            underline: underline > 0,
+            strikethrough: strikethrough > 0,
+        });
+    }
+    Ok(spans)
+}
+
+fn markup_err(msg: &str, tag: &str) -> Error {
+    Error::TextRender(format!("text markup error: {} [{}]", msg, tag))
+}
+
+/// parse a colour in the form `#rrggbb` or `#rrggbbaa`
+fn parse_colour(s: &str) -> Option<Colour> {
+    let hex = s.strip_prefix('#')?;
+    if (hex.len() != 6 && hex.len() != 8) || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Colour::new(
+        channel(0)?,
+        channel(2)?,
+        channel(4)?,
+        if hex.len() == 8 { channel(6)? } else { 255 },
+    ))
+}
+
+#[cfg(test)]
+mod rich_text_tests {
+    use super::*;
+
+    fn span(text: &str, colour: Colour, bold: bool, italic: bool) -> TextSpan {
+        TextSpan { text: text.to_string(), colour, bold, italic, underline: false, strikethrough: false }
+    }
+
+    #[test]
+    fn test_nested_styles() {
+        let white = Colour::white();
+        let spans = parse_markup("a[b]b[i]c[/i]d[/b]e", white).unwrap();
+        assert!(spans == vec![
+            span("a", white, false, false),
+            span("b", white, true, false),
+            span("c", white, true, true),
+            span("d", white, true, false),
+            span("e", white, false, false),
+        ]);
+        // the same style opened twice lasts until both are closed
+        let spans = parse_markup("[b]a[b]b[/b]c[/b]d", white).unwrap();
+        assert!(spans.iter().map(|s| s.bold).collect::<Vec<_>>() == vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn test_nested_colours() {
+        let white = Colour::white();
+        let red = Colour::new(255, 0, 0, 255);
+        let green = Colour::new(0, 255, 0, 128);
+        let spans = parse_markup("[c=#ff0000]r[c=#00ff0080]g[/c]r[/c]w", white).unwrap();
+        assert!(spans.iter().map(|s| s.colour).collect::<Vec<_>>() == vec![red, green, red, white]);
+    }
+
+    #[test]
+    fn test_unclosed_tags() {
+        let white = Colour::white();
+        // tags left open apply until the end
+        assert!(parse_markup("a[i]b", white).unwrap() == vec![
+            span("a", white, false, false),
+            span("b", white, false, true),
+        ]);
+        assert!(parse_markup("a[b", white).is_err());
+        assert!(parse_markup("a[/b]", white).is_err());
+        assert!(parse_markup("a[/c]", white).is_err());
+        assert!(parse_markup("[x]a", white).is_err());
+        assert!(parse_markup("[c=#ff00]a", white).is_err());
+    }
+
+    #[test]
+    fn test_escaped_brackets() {
+        let white = Colour::white();
+        assert!(parse_markup("a[[b] c]", white).unwrap() == vec![span("a[b] c]", white, false, false)]);
+        assert!(parse_markup("[b][[[/b]", white).unwrap() == vec![span("[", white, true, false)]);
+        assert!(parse_markup("", white).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// Information about a single resource held by a manager, part of [ResourceStats]
+#[derive(Clone, Debug)]
+pub struct ResourceInfo {
+    pub id: usize,
+    /// the file the resource was loaded from,
+    /// or a description in angle brackets for resources not loaded from a file
+    pub path: String,
+    /// estimated memory used by the resource, in bytes
+    pub bytes: usize,
+}
+
+/// A snapshot of the resources currently loaded by a manager,
+/// returned by the `stats` function of each manager in [crate::manager].
+///
+/// Printing the stats at the end of a program lists any resources that were never unloaded.
+#[derive(Clone, Debug)]
+pub struct ResourceStats {
+    /// the type of resource, ie "Texture"
+    pub name: &'static str,
+    pub resources: Vec<ResourceInfo>,
+}
+
+impl ResourceStats {
+    pub(crate) fn new(name: &'static str) -> ResourceStats {
+        ResourceStats { name, resources: Vec::new() }
+    }
+
+    /// make stats using a manager's map of paths to resource ids,
+    /// with `bytes` estimating the size of the resource with that path and id
+    pub(crate) fn from_paths(name: &'static str,
+                             paths: &HashMap<String, usize>,
+                             bytes: impl Fn(&str, usize) -> usize) -> ResourceStats {
+        let mut stats = Self::new(name);
+        for (path, id) in paths.iter() {
+            stats.push(*id, path.clone(), bytes(path, *id));
+        }
+        stats.resources.sort_by_key(|r| r.id);
+        stats
+    }
+
+    pub(crate) fn push(&mut self, id: usize, path: String, bytes: usize) {
+        self.resources.push(ResourceInfo { id, path, bytes });
+    }
+
+    /// number of live resources
+    pub fn count(&self) -> usize {
+        self.resources.len()
+    }
+
+    /// estimated total memory used by the live resources, in bytes
+    pub fn bytes(&self) -> usize {
+        self.resources.iter().map(|r| r.bytes).sum()
+    }
+}
+
+impl fmt::Display for ResourceStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {} resources loaded - {} bytes", self.count(), self.name, self.bytes())?;
+        for r in self.resources.iter() {
+            writeln!(f, "  id: {} - {} bytes - path: {}", r.id, r.bytes, r.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// size of a file in bytes, or 0 if it can't be read
+pub(crate) fn file_size(path: &str) -> usize {
+    std::fs::metadata(path).map(|m| m.len() as usize).unwrap_or(0)
+}
@@ -0,0 +1,115 @@
+use std::f64::consts::TAU;
+
+use crate::{Colour, Pixels, resources::pixels::blend_over};
+use crate::geometry::*;
+
+/// A copy of the text drawn behind it, moved by an offset
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextShadow {
+    /// how far the shadow is moved from the text, as a multiple of the text height
+    pub offset: Vec2,
+    pub colour: Colour,
+}
+
+/// A border drawn around the glyphs of the text
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextOutline {
+    /// the width of the border, as a multiple of the text height
+    pub thickness: f64,
+    pub colour: Colour,
+}
+
+/// Effects drawn behind text to keep it readable over busy backgrounds
+///
+/// Used by [crate::manager::FontManager::set_text_effects]
+/// and [crate::Camera::draw_disposable_text_with_effects].
+/// If there is a shadow and an outline, the shadow is of the outlined text.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct TextEffects {
+    pub shadow: Option<TextShadow>,
+    pub outline: Option<TextOutline>,
+}
+
+impl TextEffects {
+    pub fn with_shadow(self, offset: Vec2, colour: Colour) -> Self {
+        TextEffects { shadow: Some(TextShadow { offset, colour }), ..self }
+    }
+
+    pub fn with_outline(self, thickness: f64, colour: Colour) -> Self {
+        TextEffects { outline: Some(TextOutline { thickness, colour }), ..self }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.shadow.is_none() && self.outline.is_none()
+    }
+
+    /// the offsets in pixels and colours of the copies of the text to draw before the text,
+    /// for text that is `height` pixels tall
+    pub(crate) fn copies(&self, height: f64) -> Vec<(Vec2, Colour)> {
+        let outline = match self.outline {
+            Some(o) => outline_offsets(o.thickness * height),
+            None => Vec::new(),
+        };
+        let mut copies = Vec::new();
+        if let Some(shadow) = self.shadow {
+            let offset = shadow.offset * height;
+            copies.push((offset, shadow.colour));
+            copies.extend(outline.iter().map(|o| (offset + *o, shadow.colour)));
+        }
+        if let Some(o) = self.outline {
+            copies.extend(outline.iter().map(|offset| (*offset, o.colour)));
+        }
+        copies
+    }
+
+    /// draw the effects around the rendered text,
+    /// returning the new pixels and the space added to the left, top, right and bottom
+    pub(crate) fn apply(&self, text: &Pixels) -> (Pixels, [u32; 4]) {
+        let copies: Vec<(i32, i32, Colour)> = self.copies(text.height as f64).iter()
+            .map(|(o, c)| (o.x.round() as i32, o.y.round() as i32, *c))
+            .collect();
+        let min_x = copies.iter().map(|c| c.0).min().unwrap_or(0).min(0);
+        let min_y = copies.iter().map(|c| c.1).min().unwrap_or(0).min(0);
+        let max_x = copies.iter().map(|c| c.0).max().unwrap_or(0).max(0);
+        let max_y = copies.iter().map(|c| c.1).max().unwrap_or(0).max(0);
+        let width = text.width + (max_x - min_x) as u32;
+        let height = text.height + (max_y - min_y) as u32;
+        let mut out = Pixels { width, height, rgba: vec![0; (width * height * 4) as usize] };
+        let mut stamp = |ox: i32, oy: i32, colour: Option<Colour>| {
+            for y in 0..text.height {
+                for x in 0..text.width {
+                    let i = ((y * text.width + x) * 4) as usize;
+                    let src = match colour {
+                        // copies only use the shape of the glyphs
+                        Some(c) => [c.r, c.g, c.b, (text.rgba[i + 3] as u32 * c.a as u32 / 255) as u8],
+                        None => [text.rgba[i], text.rgba[i + 1], text.rgba[i + 2], text.rgba[i + 3]],
+                    };
+                    let (tx, ty) = ((x as i32 + ox - min_x) as u32, (y as i32 + oy - min_y) as u32);
+                    let dst = ((ty * width + tx) * 4) as usize;
+                    blend_over(&mut out.rgba[dst..dst + 4], src);
+                }
+            }
+        };
+        for (ox, oy, colour) in copies {
+            stamp(ox, oy, Some(colour));
+        }
+        stamp(0, 0, None);
+        (out, [(-min_x) as u32, (-min_y) as u32, max_x as u32, max_y as u32])
+    }
+}
+
+/// offsets in rings around the text out to `radius` pixels,
+/// close enough together that the copies of the text leave no gaps
+fn outline_offsets(radius: f64) -> Vec<Vec2> {
+    let mut offsets = Vec::new();
+    let mut r = radius;
+    while r > 0.0 {
+        let count = ((TAU * r / 2.0).ceil() as usize).max(8);
+        for i in 0..count {
+            let angle = TAU * i as f64 / count as f64;
+            offsets.push(Vec2::new(angle.cos() * r, angle.sin() * r));
+        }
+        r -= 2.0;
+    }
+    offsets
+}
@@ -0,0 +1,186 @@
+use crate::geometry::*;
+
+/// How lines of text are placed horizontally within a [TextLayout]'s rect
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HorizontalAlign {
+    Left,
+    Centre,
+    Right,
+    /// stretch the gaps between words so that wrapped lines fill the width,
+    /// the last line of each paragraph is left aligned
+    Justify,
+}
+
+/// How the block of lines is placed vertically within a [TextLayout]'s rect
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VerticalAlign {
+    Top,
+    Centre,
+    Bottom,
+}
+
+/// Settings for laying out multiple lines of text,
+/// used by [crate::manager::FontManager::load_text_layout]
+#[derive(Clone, Copy)]
+pub struct TextLayout {
+    /// the area the text is aligned in
+    pub rect: Rect,
+    /// if true, lines longer than the rect's width are broken between words
+    pub wrap: bool,
+    /// the distance between lines, as a multiple of the text height
+    pub line_spacing: f64,
+    pub horizontal_align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+}
+
+impl TextLayout {
+    /// a layout with wrapping in the rect, aligned to the top left with single spacing
+    pub fn new(rect: Rect) -> TextLayout {
+        TextLayout {
+            rect,
+            wrap: true,
+            line_spacing: 1.0,
+            horizontal_align: HorizontalAlign::Left,
+            vertical_align: VerticalAlign::Top,
+        }
+    }
+
+    /// Place each piece of the text in the layout,
+    /// where `measure` gives the width of a string at the text height
+    ///
+    /// Returns each piece of text with its position,
+    /// lines are split into words only when they are justified.
+    pub(crate) fn arrange(&self,
+                          text: &str,
+                          height: f64,
+                          measure: impl Fn(&str) -> f64) -> Vec<(String, Vec2)> {
+        let lines = self.break_lines(text, &measure);
+        let line_height = height * self.line_spacing;
+        let block_height = height + line_height * (lines.len().max(1) - 1) as f64;
+        let mut y = match self.vertical_align {
+            VerticalAlign::Top => self.rect.y,
+            VerticalAlign::Centre => self.rect.y + (self.rect.h - block_height) / 2.0,
+            VerticalAlign::Bottom => self.rect.y + self.rect.h - block_height,
+        };
+        let mut pieces = Vec::new();
+        for (line, paragraph_end) in lines {
+            let words: Vec<&str> = line.split(' ').filter(|w| !w.is_empty()).collect();
+            if self.horizontal_align == HorizontalAlign::Justify && !paragraph_end && words.len() > 1 {
+                let widths: Vec<f64> = words.iter().map(|w| measure(w)).collect();
+                let gap = (self.rect.w - widths.iter().sum::<f64>()) / (words.len() - 1) as f64;
+                let mut x = self.rect.x;
+                for (w, width) in words.iter().zip(widths) {
+                    pieces.push((w.to_string(), Vec2::new(x, y)));
+                    x += width + gap;
+                }
+            } else if !line.is_empty() {
+                let width = measure(&line);
+                let x = match self.horizontal_align {
+                    HorizontalAlign::Left | HorizontalAlign::Justify => self.rect.x,
+                    HorizontalAlign::Centre => self.rect.x + (self.rect.w - width) / 2.0,
+                    HorizontalAlign::Right => self.rect.x + self.rect.w - width,
+                };
+                pieces.push((line, Vec2::new(x, y)));
+            }
+            y += line_height;
+        }
+        pieces
+    }
+
+    /// split the text into lines, marking the lines that end a paragraph
+    fn break_lines(&self, text: &str, measure: &impl Fn(&str) -> f64) -> Vec<(String, bool)> {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            if !self.wrap {
+                lines.push((paragraph.to_string(), true));
+                continue;
+            }
+            let mut line = String::new();
+            // runs of spaces are collapsed when wrapping, so lines don't start or end with gaps
+            for word in paragraph.split(' ').filter(|w| !w.is_empty()) {
+                let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+                // words wider than the rect are given a line of their own
+                if !line.is_empty() && measure(&candidate) > self.rect.w {
+                    lines.push((line, false));
+                    line = word.to_string();
+                } else {
+                    line = candidate;
+                }
+            }
+            lines.push((line, true));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod text_layout_tests {
+    use super::*;
+
+    /// every character is 10 wide
+    fn measure(s: &str) -> f64 {
+        s.chars().count() as f64 * 10.0
+    }
+
+    fn layout(w: f64, h: f64) -> TextLayout {
+        TextLayout::new(Rect::new(5.0, 20.0, w, h))
+    }
+
+    #[test]
+    fn test_wrap_width() {
+        let pieces = layout(100.0, 100.0).arrange("aaaa bbbb cccc dddddddddddd e", 10.0, measure);
+        let lines: Vec<&str> = pieces.iter().map(|(t, _)| t.as_str()).collect();
+        assert!(lines == vec!["aaaa bbbb", "cccc", "dddddddddddd", "e"]);
+        // only words wider than the rect make longer lines
+        assert!(pieces.iter().all(|(t, _)| measure(t) <= 100.0 || !t.contains(' ')));
+        let no_wrap = TextLayout { wrap: false, ..layout(100.0, 100.0) };
+        assert!(no_wrap.arrange("aaaa bbbb cccc", 10.0, measure).len() == 1);
+    }
+
+    #[test]
+    fn test_collapse_spaces() {
+        let pieces = layout(100.0, 100.0).arrange("   aaaa     bbbb   ", 10.0, measure);
+        assert!(pieces.len() == 1);
+        assert!(pieces[0].0 == "aaaa bbbb");
+        assert!(pieces[0].1 == Vec2::new(5.0, 20.0));
+        assert!(layout(100.0, 100.0).arrange("     ", 10.0, measure).is_empty());
+    }
+
+    #[test]
+    fn test_line_count() {
+        let l = TextLayout { line_spacing: 1.5, ..layout(100.0, 100.0) };
+        let pieces = l.arrange("a\n\nb\nc", 10.0, measure);
+        let ys: Vec<f64> = pieces.iter().map(|(_, p)| p.y).collect();
+        // the empty line still takes up space
+        assert!(ys == vec![20.0, 50.0, 65.0]);
+    }
+
+    #[test]
+    fn test_alignment() {
+        let centre = TextLayout {
+            horizontal_align: HorizontalAlign::Centre,
+            vertical_align: VerticalAlign::Centre,
+            ..layout(100.0, 100.0)
+        };
+        let pieces = centre.arrange("aaaa\nbb", 10.0, measure);
+        assert!(pieces[0].1 == Vec2::new(35.0, 60.0));
+        assert!(pieces[1].1 == Vec2::new(45.0, 70.0));
+        let right = TextLayout {
+            horizontal_align: HorizontalAlign::Right,
+            vertical_align: VerticalAlign::Bottom,
+            ..layout(100.0, 100.0)
+        };
+        let pieces = right.arrange("aaaa", 10.0, measure);
+        assert!(pieces[0].1 == Vec2::new(65.0, 110.0));
+    }
+
+    #[test]
+    fn test_justify() {
+        let justify = TextLayout { horizontal_align: HorizontalAlign::Justify, ..layout(100.0, 100.0) };
+        let pieces = justify.arrange("aa  bb cc dddddd", 10.0, measure);
+        // the first line is stretched to the width, the last line of the paragraph is not
+        let first: Vec<(&str, f64)> = pieces.iter().take(3).map(|(t, p)| (t.as_str(), p.x)).collect();
+        assert!(first == vec![("aa", 5.0), ("bb", 45.0), ("cc", 85.0)]);
+        assert!(pieces[3].0 == "dddddd" && pieces[3].1 == Vec2::new(5.0, 30.0));
+    }
+}
@@ -0,0 +1,37 @@
+use crate::geometry::*;
+
+/// The size of a string of text and where the caret can be placed between its characters,
+/// returned by [crate::manager::FontManager::measure_chars]
+#[derive(Clone)]
+pub struct TextMetrics {
+    /// the width and height of the whole text
+    pub size: Vec2,
+    /// the x offset from the start of the text of the caret before each character,
+    /// with a final entry for the caret after the last character
+    pub carets: Vec<f64>,
+}
+
+impl TextMetrics {
+    /// the horizontal distance the caret moves past each character
+    pub fn advances(&self) -> Vec<f64> {
+        self.carets.windows(2).map(|c| c[1] - c[0]).collect()
+    }
+
+    /// the index of the caret closest to the x offset from the start of the text,
+    /// for finding where in the text a click landed
+    pub fn caret_at(&self, x: f64) -> usize {
+        let mut closest = 0;
+        for (i, c) in self.carets.iter().enumerate() {
+            if (c - x).abs() < (self.carets[closest] - x).abs() {
+                closest = i;
+            }
+        }
+        closest
+    }
+
+    /// the index of the character under the x offset from the start of the text,
+    /// or `None` if the offset is outside of the text
+    pub fn char_at(&self, x: f64) -> Option<usize> {
+        self.carets.windows(2).position(|c| x >= c[0] && x < c[1])
+    }
+}
@@ -1,5 +1,5 @@
-use sdl2::render::{TextureCreator, Texture, Canvas};
-use sdl2::{image::LoadTexture, video::Window}; 
+use sdl2::render::{TextureCreator, Texture, Canvas, BlendMode};
+use sdl2::{image::{LoadTexture, LoadSurface}, video::Window, pixels::PixelFormatEnum, surface::Surface};
 
 use std::collections::HashMap;
 use std::path::Path;
@@ -11,7 +11,10 @@ use crate::{
     error::Error,
     GameObject,
     resources::types::TextureDraw,
-    unload_resource, load, file_err, draw_err, draw
+    resources::pixels::Pixels,
+    resources::palette::Palette,
+    resources::stats::ResourceStats,
+    unload_resource, load, file_err, draw_err, draw, load_resource_helper, helper_err
 };
 
 use crate::geometry::*;
@@ -22,6 +25,8 @@ pub struct TextureManager<'a, T> {
     texture_creator : &'a TextureCreator<T>,
     loaded_texture_paths : HashMap<String,  usize>,
     textures     : Vec<Option<Texture<'a>>>,
+    /// swapped textures keyed by the source texture's path, as ids are reused after unloading
+    palette_swaps: HashMap<(String, Palette), (String, resource::Texture)>,
 }
 
 impl<'a, T> TextureManager<'a, T> {
@@ -38,6 +43,91 @@ impl<'a, T> TextureManager<'a, T> {
         })
     }
 
+    /// Load a texture like `load`, and also get a copy of the image's [Pixels].
+    ///
+    /// The pixels can be used to read colours from the image on the cpu,
+    /// or to build a [crate::CollisionMask] for pixel perfect collisions.
+    pub fn load_with_pixels(&mut self, path : &Path) -> Result<(resource::Texture, Pixels), Error> {
+        // decode the image once for both the texture and the pixels
+        let surface = file_err!(Surface::from_file(path))?;
+        let pixels = Pixels::from_surface(&surface)?;
+        let tex_index = crate::load_resource!(path, self.textures, self.loaded_texture_paths, "Texture",
+            Some(helper_err!(self.texture_creator.create_texture_from_surface(&surface), LoadFile)?));
+        let loaded_tex = self.textures[tex_index].as_ref().unwrap();
+        Ok((
+        resource::Texture {
+            id: tex_index,
+            width: loaded_tex.query().width,
+            height: loaded_tex.query().height,
+        }, pixels))
+    }
+
+    /// Create a blank streaming texture and fill it with the supplied pixel buffer.
+    ///
+    /// - `rgba` holds 4 bytes per pixel in the order red, green, blue, alpha,
+    ///   with rows stored top to bottom, so it must be `width * height * 4` bytes long
+    ///
+    /// The returned [resource::Texture] can be used like a loaded one,
+    /// and its pixels can be changed later with `update_texture`.
+    pub fn create_texture(&mut self, width: u32, height: u32, rgba: &[u8]) -> Result<resource::Texture, Error> {
+        // generated textures have no file, but need a key so that `unload` can find them
+        self.create_texture_with_key(width, height, rgba, |index| format!("<generated texture {}>", index))
+    }
+
+    /// Replace all of the pixels of a texture with the supplied pixel buffer.
+    ///
+    /// This is intended for textures made with `create_texture`, so that they
+    /// can be changed every frame for effects like noise or fog of war masks.
+    /// The buffer has the same layout as in `create_texture`, and must match the texture's size.
+    pub fn update_texture(&mut self, tex: resource::Texture, rgba: &[u8]) -> Result<(), Error> {
+        check_pixel_buffer(tex.width, tex.height, rgba)?;
+        self.use_texture(tex, |t| {
+            helper_err!(t.update(None, rgba, tex.width as usize * 4), TextureCreate)
+        })
+    }
+
+    /// Get a copy of a texture with its colours replaced using a [Palette].
+    ///
+    /// The recoloured texture is cached, so calling this again with the same texture and palette
+    /// returns the same [resource::Texture] without making a new one.
+    /// This is useful for team colours or hit flashes without extra image files.
+    ///
+    /// The source texture must have been loaded from a file, as its pixels are read from there.
+    pub fn palette_swap(&mut self, tex: resource::Texture, palette: &Palette) -> Result<resource::Texture, Error> {
+        let path = match self.loaded_texture_paths.iter().find(|(k, v)| **v == tex.id && !k.starts_with('<')) {
+            Some((k, _)) => k.clone(),
+            None => return Err(Error::MissingResource(
+                "palette swaps need a texture that is loaded from a file".to_string())),
+        };
+        let cache_key = (path.clone(), palette.clone());
+        if let Some((key, swapped)) = self.palette_swaps.get(&cache_key) {
+            // the swapped texture may have been unloaded since it was cached
+            if self.loaded_texture_paths.get(key) == Some(&swapped.id) {
+                return Ok(*swapped);
+            }
+        }
+        let mut pixels = Pixels::load(Path::new(&path))?;
+        palette.apply(&mut pixels.rgba);
+        let mut key = String::new();
+        let swapped = self.create_texture_with_key(
+            pixels.width, pixels.height, &pixels.rgba,
+            |index| { key = format!("<palette swap {} of {}>", index, path); key.clone() })?;
+        // drop any stale swap that was unloaded from the slot this one reused
+        self.palette_swaps.retain(|_, (_, t)| t.id != swapped.id);
+        self.palette_swaps.insert(cache_key, (key, swapped));
+        Ok(swapped)
+    }
+
+    /// Get the textures currently loaded, with their estimated gpu memory use
+    pub fn stats(&self) -> ResourceStats {
+        ResourceStats::from_paths("Texture", &self.loaded_texture_paths, |_, id| {
+            match &self.textures[id] {
+                Some(t) => t.query().width as usize * t.query().height as usize * 4,
+                None => 0,
+            }
+        })
+    }
+
     /// Calls `unload` with the texture attached to the [GameObject].
     pub fn unload_from_gameobject(&mut self, game_object: GameObject) {
         self.unload(game_object.get_texture());
@@ -52,6 +142,7 @@ impl<'a, T> TextureManager<'a, T> {
             texture_creator : tex_creator,
             loaded_texture_paths: HashMap::new(),
             textures : Vec::new(),
+            palette_swaps: HashMap::new(),
         }
     }
 
@@ -59,6 +150,25 @@ impl<'a, T> TextureManager<'a, T> {
         fn draw(self, tex_draw : TextureDraw) 
             self.textures
     }
+
+    fn create_texture_with_key(&mut self,
+                               width: u32,
+                               height: u32,
+                               rgba: &[u8],
+                               key: impl FnOnce(usize) -> String) -> Result<resource::Texture, Error> {
+        check_pixel_buffer(width, height, rgba)?;
+        let tex = streaming_texture(self.texture_creator, width, height, rgba)?;
+        let index = load_resource_helper!(check_and_push(self.textures, Some(tex)));
+        self.loaded_texture_paths.insert(key(index), index);
+        log::info!("created texture - id: {} - size: {}x{}", index, width, height);
+        Ok(resource::Texture { id: index, width, height })
+    }
+
+    fn use_texture<R>(&mut self,
+                      tex: resource::Texture,
+                      f: impl FnOnce(&mut Texture<'a>) -> Result<R, Error>) -> Result<R, Error> {
+        crate::use_resource!(self.textures, tex.id, Some(t) => { f(t) })
+    }
     
     pub(crate) fn draw_rect(&self,
                             canvas : &mut Canvas<Window>,
@@ -69,3 +179,28 @@ impl<'a, T> TextureManager<'a, T> {
         Ok(())
     }
 }
+
+fn check_pixel_buffer(width: u32, height: u32, rgba: &[u8]) -> Result<(), Error> {
+    if width == 0 || height == 0 {
+        return Err(Error::TextureCreate("texture width and height must be greater than 0".to_string()));
+    }
+    if rgba.len() != width as usize * height as usize * 4 {
+        return Err(Error::TextureCreate(format!(
+            "pixel buffer of {} bytes does not match a {}x{} rgba texture",
+            rgba.len(), width, height)));
+    }
+    Ok(())
+}
+
+/// a texture with alpha blending holding the rgba pixels, which can be updated later
+pub(crate) fn streaming_texture<'a, T>(texture_creator: &'a TextureCreator<T>,
+                                       width: u32,
+                                       height: u32,
+                                       rgba: &[u8]) -> Result<Texture<'a>, Error> {
+    let mut tex = helper_err!(
+        texture_creator.create_texture_streaming(PixelFormatEnum::RGBA32, width, height),
+        TextureCreate)?;
+    tex.set_blend_mode(BlendMode::Blend);
+    helper_err!(tex.update(None, rgba, width as usize * 4), TextureCreate)?;
+    Ok(tex)
+}
@@ -4,7 +4,7 @@ use crate::resource;
 
 
 /// An RGBA colour with values from `0` to `255` for each channel
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct Colour {
     pub r: u8,
     pub g: u8,
@@ -38,7 +38,7 @@ impl Colour {
     }
 }
 
-/// used by [crate::camera::Camera] for drawing texures with texture rects
+/// used by [crate::Camera] for drawing texures with texture rects
 /// and draw rects and a colour.
 #[derive(Clone, Copy)]
 pub struct GameObject {
@@ -81,10 +81,13 @@ impl GameObject {
     pub(crate) fn get_texture(&self) -> resource::Texture {
         self.texture
     }
+    pub(crate) fn set_texture(&mut self, texture: resource::Texture) {
+        self.texture = texture;
+    }
 }
 
 
-/// Used by [crate::camera::Camera]. An object that stores text
+/// Used by [crate::Camera]. An object that stores text
 /// with some drawing settings for rendering.
 pub type TextObject = GameObject;
 